@@ -0,0 +1,50 @@
+use crate::config;
+use chrono::Utc;
+use color_eyre::eyre::{Result, WrapErr};
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+use std::{fs, path::PathBuf};
+
+/// Value with the time it was cached.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Cached<T> {
+    /// Unix timestamp in seconds
+    pub cached_at: i64,
+    pub value: T,
+}
+
+impl<T> Cached<T> {
+    pub fn new(value: T) -> Self {
+        Cached {
+            cached_at: Utc::now().timestamp(),
+            value,
+        }
+    }
+
+    pub fn age_seconds(&self) -> i64 {
+        Utc::now().timestamp() - self.cached_at
+    }
+
+    pub fn is_fresh(&self, max_age_seconds: i64) -> bool {
+        self.age_seconds() <= max_age_seconds
+    }
+}
+
+fn cache_file(name: &str) -> PathBuf {
+    config::cache_dir().join(format!("{name}.json"))
+}
+
+/// Missing or unreadable caches are treated as empty
+pub fn read<T: DeserializeOwned>(name: &str) -> Option<T> {
+    fs::read_to_string(cache_file(name))
+        .ok()
+        .and_then(|cache| serde_json::from_str(&cache).ok())
+}
+
+pub fn write<T: Serialize>(name: &str, value: &T) -> Result<()> {
+    let file = cache_file(name);
+    if let Some(dir) = file.parent() {
+        fs::create_dir_all(dir).wrap_err("Failed to create cache directory")?;
+    }
+    let cache = serde_json::to_string_pretty(value).wrap_err("Failed to serialize cache")?;
+    fs::write(file, cache).wrap_err(format!("Failed to write {name} cache"))
+}
@@ -0,0 +1,336 @@
+use crate::config::{Config, ProxyConfig};
+use color_eyre::eyre::{Result, WrapErr};
+use jira::{Credential, JiraAPIClient, JiraClientError, models::*};
+use reqwest::header::{ACCEPT, AUTHORIZATION, CONTENT_TYPE, HeaderMap, HeaderValue};
+use reqwest::{
+    Certificate, Client, ClientBuilder, Identity, NoProxy, Proxy, Response, StatusCode, Url,
+};
+use serde::{Deserialize, Serialize};
+use std::{ops::Deref, time::Duration};
+
+/// Response of /rest/api/latest/serverInfo
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ServerInfo {
+    pub version: String,
+    pub server_title: Option<String>,
+}
+
+/// Issue status including its category, which the jira-issue-api Status model lacks
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct IssueStatus {
+    pub name: String,
+    pub status_category: StatusCategory,
+}
+
+/// Key is one of new, indeterminate or done
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct StatusCategory {
+    pub key: String,
+    pub name: String,
+}
+
+/// Version of a project, as used in fixVersions
+#[derive(Deserialize, Debug, Clone)]
+pub struct ProjectVersion {
+    pub name: String,
+}
+
+#[derive(Deserialize)]
+struct IssueStatusResponse {
+    fields: IssueStatusFields,
+}
+
+#[derive(Deserialize)]
+struct IssueStatusFields {
+    status: IssueStatus,
+}
+
+/// JiraAPIClient extended with the endpoints jira-issue-api lacks.
+///
+/// The extra endpoints use their own client, built with the TLS and proxy setup
+/// (custom CA bundles, client certificates and authenticating proxies) from the config.
+#[derive(Debug, Clone)]
+pub struct JiraClient {
+    pub url: Url,
+
+    api: JiraAPIClient,
+    client: Client,
+    anonymous_access: bool,
+}
+
+impl Deref for JiraClient {
+    type Target = JiraAPIClient;
+
+    fn deref(&self) -> &Self::Target {
+        &self.api
+    }
+}
+
+/// None leaves proxy selection to reqwest, which honours the proxy environment variables.
+pub fn build_proxy(proxy_cfg: &ProxyConfig) -> Result<Option<Proxy>> {
+    if !proxy_cfg.is_configured() {
+        return Ok(None);
+    }
+    let env = |var: &str| std::env::var(var).ok();
+    let Some((url, source)) = proxy_cfg.effective_url(env) else {
+        return Ok(None);
+    };
+
+    let mut proxy = Proxy::all(&url).wrap_err(format!("Invalid proxy url from {source}: {url}"))?;
+    if let Some(username) = &proxy_cfg.username {
+        proxy = proxy.basic_auth(username, proxy_cfg.password.as_deref().unwrap_or_default());
+    }
+
+    let no_proxy = proxy_cfg
+        .effective_no_proxy(env)
+        .and_then(|hosts| NoProxy::from_string(&hosts));
+    Ok(Some(proxy.no_proxy(no_proxy)))
+}
+
+impl JiraClient {
+    fn api_url(&self, path: &str) -> Result<Url, JiraClientError> {
+        Ok(self.url.join(&format!("rest/api/latest/{path}"))?)
+    }
+
+    fn build_headers(credentials: &Credential) -> HeaderMap {
+        use base64::{Engine as _, engine::general_purpose};
+
+        let header_content = HeaderValue::from_static("application/json");
+
+        let auth_header = match credentials {
+            Credential::Anonymous => None,
+            Credential::ApiToken {
+                login: user_login,
+                token: api_token,
+            } => {
+                let jira_encoded_auth =
+                    general_purpose::STANDARD.encode(format!("{user_login}:{api_token}"));
+                HeaderValue::from_str(&format!("Basic {jira_encoded_auth}")).ok()
+            }
+            Credential::PersonalAccessToken(token) => {
+                HeaderValue::from_str(&format!("Bearer {token}")).ok()
+            }
+        };
+
+        let mut headers = HeaderMap::new();
+        headers.insert(ACCEPT, header_content.clone());
+        headers.insert(CONTENT_TYPE, header_content);
+
+        if let Some(mut auth_header_value) = auth_header {
+            auth_header_value.set_sensitive(true);
+            headers.insert(AUTHORIZATION, auth_header_value);
+        }
+
+        headers
+    }
+
+    pub fn new(cfg: &Config) -> Result<JiraClient> {
+        let jira_cfg = &cfg.jira_cfg;
+        let mut builder = ClientBuilder::new()
+            .default_headers(JiraClient::build_headers(&jira_cfg.credential))
+            .https_only(true)
+            .timeout(Duration::from_secs(jira_cfg.timeout))
+            .danger_accept_invalid_certs(jira_cfg.insecure_skip_tls_verify)
+            .connection_verbose(false);
+
+        if let Some(ca) = &jira_cfg.ca_certificate {
+            let certs = Certificate::from_pem_bundle(ca.as_bytes())
+                .wrap_err("Unable to parse CA certificate(s)")?;
+            builder = builder.tls_certs_only(certs);
+        }
+
+        if let Some(identity) = &cfg.tls_client_identity {
+            builder = builder.identity(
+                Identity::from_pem(identity.as_bytes())
+                    .wrap_err("Unable to parse client certificate")?,
+            );
+        }
+
+        if let Some(proxy) = build_proxy(&cfg.proxy_cfg)? {
+            builder = builder.proxy(proxy);
+        }
+
+        let mut url = Url::parse(&jira_cfg.url).wrap_err("Unable to parse jira_url")?;
+        url.set_path("/");
+        url.set_query(None);
+        url.set_fragment(None);
+
+        Ok(JiraClient {
+            url,
+            api: JiraAPIClient::new(jira_cfg).wrap_err("Failed to construct API client")?,
+            client: builder.build().wrap_err("Failed to construct API client")?,
+            anonymous_access: jira_cfg.credential.eq(&Credential::Anonymous),
+        })
+    }
+
+    /// Jira answers searches with failed credentials as anonymous instead of failing them
+    fn is_authentication_failure(&self, res: &Response) -> bool {
+        !self.anonymous_access
+            && (res
+                .headers()
+                .get("x-seraph-loginreason")
+                .is_some_and(|e| e.to_str().unwrap_or_default() == "AUTHENTICATED_FAILED")
+                || res
+                    .headers()
+                    .get("x-ausername")
+                    .is_some_and(|e| e.to_str().unwrap_or_default() == "anonymous"))
+    }
+
+    /// Issues for `issue_keys`, in pages of the server's maximum results.
+    /// Keys that do not exist are left out instead of failing the query.
+    pub async fn query_issue_keys(
+        &self,
+        issue_keys: &[IssueKey],
+        fields: &[&str],
+    ) -> Result<Vec<Issue>, JiraClientError> {
+        let url = self.api_url("search")?;
+        let keys = issue_keys
+            .iter()
+            .map(|key| key.to_string())
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        let mut issues = vec![];
+        loop {
+            let body = serde_json::json!({
+                "jql": format!("key in ({keys}) ORDER BY key"),
+                "startAt": issues.len(),
+                "maxResults": issue_keys.len(),
+                "fields": fields,
+                "validateQuery": "warn",
+            });
+
+            let res = self.client.post(url.clone()).json(&body).send().await?;
+            if self.is_authentication_failure(&res) {
+                return Err(JiraClientError::JiraQueryAuthenticationError());
+            }
+            let page = res
+                .error_for_status()?
+                .json::<PostIssueQueryResponseBody>()
+                .await?;
+
+            let page_issues = page.issues.unwrap_or_default();
+            let total = page.total.unwrap_or_default() as usize;
+            let done = page_issues.is_empty();
+            issues.extend(page_issues);
+            if done || issues.len() >= total {
+                return Ok(issues);
+            }
+        }
+    }
+
+    /// Does not require authentication.
+    pub async fn get_server_info(&self) -> Result<ServerInfo, JiraClientError> {
+        let url = self.api_url("serverInfo")?;
+
+        let response = self.client.get(url).send().await?.error_for_status()?;
+        let body = response.json::<ServerInfo>().await?;
+        Ok(body)
+    }
+
+    /// The user owning the configured credential.
+    pub async fn get_myself(&self) -> Result<User, JiraClientError> {
+        let url = self.api_url("myself")?;
+
+        let response = self.client.get(url).send().await?;
+        if matches!(
+            response.status(),
+            StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN
+        ) {
+            return Err(JiraClientError::JiraQueryAuthenticationError());
+        }
+
+        let body = response.error_for_status()?.json::<User>().await?;
+        Ok(body)
+    }
+
+    /// Status of the issue, None if it does not exist or is not visible to the user.
+    pub async fn get_issue_status(
+        &self,
+        issue_key: &IssueKey,
+    ) -> Result<Option<IssueStatus>, JiraClientError> {
+        let mut url = self.api_url(&format!("issue/{issue_key}"))?;
+        url.set_query(Some("fields=status"));
+
+        let response = self.client.get(url).send().await?;
+        if response.status() == StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+
+        let body = response
+            .error_for_status()?
+            .json::<IssueStatusResponse>()
+            .await?;
+        Ok(Some(body.fields.status))
+    }
+
+    /// Released and unreleased versions of the project
+    pub async fn get_project_versions(
+        &self,
+        project: &str,
+    ) -> Result<Vec<ProjectVersion>, JiraClientError> {
+        let url = self.api_url(&format!("project/{project}/versions"))?;
+
+        let response = self.client.get(url).send().await?;
+        let body = response
+            .error_for_status()?
+            .json::<Vec<ProjectVersion>>()
+            .await?;
+        Ok(body)
+    }
+
+    pub async fn post_version(
+        &self,
+        project: &str,
+        name: &str,
+    ) -> Result<ProjectVersion, JiraClientError> {
+        let url = self.api_url("version")?;
+
+        let body = serde_json::json!({ "name": name, "project": project });
+        let response = self.client.post(url).json(&body).send().await?;
+        let body = response
+            .error_for_status()?
+            .json::<ProjectVersion>()
+            .await?;
+        Ok(body)
+    }
+
+    /// Add `version` to the fix versions of the issue, keeping the existing ones
+    pub async fn put_fix_version(
+        &self,
+        issue_key: &IssueKey,
+        version: &str,
+    ) -> Result<(), JiraClientError> {
+        let url = self.api_url(&format!("issue/{issue_key}"))?;
+
+        let body = serde_json::json!({
+            "update": { "fixVersions": [{ "add": { "name": version } }] }
+        });
+        self.client
+            .put(url)
+            .json(&body)
+            .send()
+            .await?
+            .error_for_status()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn basic_auth_is_padded() {
+        let headers = JiraClient::build_headers(&Credential::ApiToken {
+            login: String::from("user"),
+            token: String::from("token1"),
+        });
+        assert_eq!(
+            "Basic dXNlcjp0b2tlbjE=",
+            headers.get(AUTHORIZATION).unwrap().to_str().unwrap()
+        );
+    }
+}
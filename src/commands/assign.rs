@@ -1,13 +1,13 @@
 use std::fmt::{Display, Formatter};
 
-use crate::{config::Config, interactivity::issue_from_branch_or_prompt, repo::Repository};
+use crate::{
+    client::JiraClient, config::Config, interactivity::issue_from_branch_or_prompt,
+    repo::Repository,
+};
 use clap::{Args, ValueHint};
 use color_eyre::eyre::{Result, WrapErr, eyre};
 use inquire::{Select, Text};
-use jira::{
-    JiraAPIClient,
-    models::{GetAssignableUserParams, IssueKey, User},
-};
+use jira::models::{GetAssignableUserParams, IssueKey, User};
 
 use super::shared::ExecCommand;
 
@@ -24,7 +24,7 @@ pub struct Assign {
 
 impl ExecCommand for Assign {
     async fn exec(self, cfg: &Config) -> Result<String> {
-        let client = JiraAPIClient::new(&cfg.jira_cfg)?;
+        let client = JiraClient::new(cfg)?;
 
         let maybe_repo = Repository::open().wrap_err("Failed to open repository");
         let head = match maybe_repo {
@@ -1,4 +1,5 @@
 use crate::{
+    client::JiraClient,
     config::Config,
     interactivity::{issue_from_branch_or_prompt, query_issue_details},
     repo::Repository,
@@ -9,7 +10,7 @@ use color_eyre::{
     eyre::{Result, WrapErr, eyre},
 };
 use inquire::Select;
-use jira::{JiraAPIClient, models::IssueKey};
+use jira::models::IssueKey;
 
 use super::shared::ExecCommand;
 
@@ -49,7 +50,7 @@ impl ExecCommand for Branch {
         }
 
         let repo = Repository::open().wrap_err("Failed to open repository")?;
-        let client = JiraAPIClient::new(&cfg.jira_cfg)?;
+        let client = JiraClient::new(cfg)?;
 
         let issue = if let Some(maybe_issue_key) = self.issue_key_input {
             let issue_key = IssueKey::try_from(maybe_issue_key)?;
@@ -0,0 +1,338 @@
+use crate::{
+    client::JiraClient,
+    config::Config,
+    hooks::CommitMsg,
+    repo::{CommitInfo, Repository},
+};
+use clap::{Args, ValueEnum, ValueHint};
+use color_eyre::eyre::{Result, WrapErr};
+use jira::models::{Issue, IssueKey};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+use super::shared::ExecCommand;
+
+/// Fields rendered in the release notes
+const CHANGELOG_FIELDS: [&str; 5] = [
+    "summary",
+    "issuetype",
+    "status",
+    "components",
+    "fixVersions",
+];
+
+#[derive(Args, Debug)]
+pub struct Changelog {
+    /// Commits to collect issues from, e.g. v1.3.0..v1.4.0
+    #[arg(value_name = "RANGE", value_hint = ValueHint::Unknown)]
+    range: String,
+
+    /// Group issues by issue type or component
+    #[arg(short, long, value_enum, default_value_t = GroupBy::Type)]
+    group_by: GroupBy,
+
+    /// Output format
+    #[arg(short, long, value_enum, default_value_t = Format::Markdown)]
+    format: Format,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum GroupBy {
+    Type,
+    Component,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum Format {
+    Markdown,
+    Text,
+    Json,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct ChangelogIssue {
+    key: String,
+    summary: String,
+    issue_type: String,
+    status: String,
+    components: Vec<String>,
+    fix_versions: Vec<String>,
+    commits: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct Group {
+    name: String,
+    issues: Vec<ChangelogIssue>,
+}
+
+#[derive(Debug, Serialize)]
+struct UnkeyedCommit {
+    id: String,
+    subject: String,
+}
+
+#[derive(Debug, Serialize)]
+struct Notes {
+    range: String,
+    groups: Vec<Group>,
+    /// Referenced by commits, but not returned by Jira
+    unknown_issues: Vec<String>,
+    commits_without_issue_key: Vec<UnkeyedCommit>,
+}
+
+/// Names of the objects in a field such as components or fixVersions
+fn names(fields: &BTreeMap<String, Value>, field: &str) -> Vec<String> {
+    match fields.get(field) {
+        Some(Value::Array(values)) => values
+            .iter()
+            .filter_map(|v| v.get("name")?.as_str().map(String::from))
+            .collect(),
+        _ => vec![],
+    }
+}
+
+impl ChangelogIssue {
+    fn from_issue(issue: Issue, commits: Vec<String>) -> Self {
+        let fields = issue.fields;
+        let mut components = fields
+            .components
+            .unwrap_or_default()
+            .into_iter()
+            .map(|c| c.name)
+            .collect::<Vec<_>>();
+        if components.is_empty() {
+            components = names(&fields.customfields, "components");
+        }
+
+        ChangelogIssue {
+            key: issue.key.to_string(),
+            summary: fields.summary.unwrap_or_default(),
+            issue_type: fields
+                .customfields
+                .get("issuetype")
+                .and_then(|t| t.get("name")?.as_str())
+                .unwrap_or("Other")
+                .to_string(),
+            status: fields.status.map(|s| s.name).unwrap_or_default(),
+            fix_versions: names(&fields.customfields, "fixVersions"),
+            components,
+            commits,
+        }
+    }
+
+    /// Status and fix versions, e.g. "Done, fix version 1.4.0"
+    fn details(&self) -> String {
+        match self.fix_versions.is_empty() {
+            true => self.status.clone(),
+            false => format!(
+                "{}, fix version {}",
+                self.status,
+                self.fix_versions.join(", ")
+            ),
+        }
+    }
+}
+
+impl Notes {
+    /// Issues grouped and sorted by group name, issues with several components are in each group
+    fn group(issues: Vec<ChangelogIssue>, group_by: GroupBy) -> Vec<Group> {
+        let mut groups: BTreeMap<String, Vec<ChangelogIssue>> = BTreeMap::new();
+        for issue in issues {
+            let names = match group_by {
+                GroupBy::Type => vec![issue.issue_type.clone()],
+                GroupBy::Component if issue.components.is_empty() => {
+                    vec![String::from("No component")]
+                }
+                GroupBy::Component => issue.components.clone(),
+            };
+            for name in names {
+                groups.entry(name).or_default().push(issue.clone());
+            }
+        }
+        groups
+            .into_iter()
+            .map(|(name, issues)| Group { name, issues })
+            .collect()
+    }
+
+    fn markdown(&self, browse_url: &str) -> String {
+        let mut lines = vec![format!("# Changes in {}", self.range)];
+        for group in &self.groups {
+            lines.push(format!("\n## {}\n", group.name));
+            lines.extend(group.issues.iter().map(|issue| {
+                format!(
+                    "- [{key}]({browse_url}{key}) {} ({})",
+                    issue.summary,
+                    issue.details(),
+                    key = issue.key
+                )
+            }));
+        }
+        if !self.unknown_issues.is_empty() {
+            lines.push(String::from("\n## Not found in Jira\n"));
+            lines.extend(self.unknown_issues.iter().map(|key| format!("- {key}")));
+        }
+        if !self.commits_without_issue_key.is_empty() {
+            lines.push(String::from("\n## Commits without issue key\n"));
+            lines.extend(
+                self.commits_without_issue_key
+                    .iter()
+                    .map(|c| format!("- {:.8} {}", c.id, c.subject)),
+            );
+        }
+        lines.join("\n")
+    }
+
+    fn text(&self) -> String {
+        let mut lines = vec![format!("Changes in {}", self.range)];
+        for group in &self.groups {
+            lines.push(format!("\n{}:", group.name));
+            lines.extend(
+                group
+                    .issues
+                    .iter()
+                    .map(|i| format!("  {} {} ({})", i.key, i.summary, i.details())),
+            );
+        }
+        if !self.unknown_issues.is_empty() {
+            lines.push(String::from("\nNot found in Jira:"));
+            lines.extend(self.unknown_issues.iter().map(|key| format!("  {key}")));
+        }
+        if !self.commits_without_issue_key.is_empty() {
+            lines.push(String::from("\nCommits without issue key:"));
+            lines.extend(
+                self.commits_without_issue_key
+                    .iter()
+                    .map(|c| format!("  {:.8} {}", c.id, c.subject)),
+            );
+        }
+        lines.join("\n")
+    }
+}
+
+impl Changelog {
+    /// Issue keys in order of first appearance with the commits referencing them,
+    /// and the commits without a key. Merge commits without a key are left out.
+    fn collect(
+        commits: &[CommitInfo],
+        cfg: &Config,
+    ) -> (Vec<(IssueKey, Vec<String>)>, Vec<UnkeyedCommit>) {
+        let mut keyed: Vec<(IssueKey, Vec<String>)> = vec![];
+        let mut unkeyed = vec![];
+        for commit in commits.iter().rev() {
+            match CommitMsg::referenced_issue_key(commit, cfg.hooks_cfg.reference_style) {
+                Some(key) => match keyed.iter_mut().find(|(k, _)| *k == key) {
+                    Some((_, ids)) => ids.push(commit.id.clone()),
+                    None => keyed.push((key, vec![commit.id.clone()])),
+                },
+                None if commit.parent_count > 1 => (),
+                None => unkeyed.push(UnkeyedCommit {
+                    id: commit.id.clone(),
+                    subject: commit.subject().to_string(),
+                }),
+            }
+        }
+        (keyed, unkeyed)
+    }
+}
+
+impl ExecCommand for Changelog {
+    async fn exec(self, cfg: &Config) -> Result<String> {
+        let repo = Repository::open().wrap_err("Failed to open repository")?;
+        let (from, to) = Repository::split_range(&self.range)?;
+        let commits = repo.get_commits(to, &[repo.rev_parse(from)?])?;
+        let (keyed, commits_without_issue_key) = Self::collect(&commits, cfg);
+
+        let client = JiraClient::new(cfg)?;
+        let issue_keys = keyed.iter().map(|(key, _)| key.clone()).collect::<Vec<_>>();
+        let mut found = match issue_keys.is_empty() {
+            true => vec![],
+            false => client
+                .query_issue_keys(&issue_keys, &CHANGELOG_FIELDS)
+                .await
+                .wrap_err("Failed to query the issues of the range")?,
+        };
+
+        let mut issues = vec![];
+        let mut unknown_issues = vec![];
+        for (key, commit_ids) in keyed {
+            match found.iter().position(|issue| issue.key == key) {
+                Some(i) => issues.push(ChangelogIssue::from_issue(found.remove(i), commit_ids)),
+                None => unknown_issues.push(key.to_string()),
+            }
+        }
+
+        let notes = Notes {
+            range: self.range.clone(),
+            groups: Notes::group(issues, self.group_by),
+            unknown_issues,
+            commits_without_issue_key,
+        };
+        match self.format {
+            Format::Markdown => Ok(notes.markdown(&format!("{}browse/", client.url))),
+            Format::Text => Ok(notes.text()),
+            Format::Json => {
+                serde_json::to_string_pretty(&notes).wrap_err("Failed to serialize release notes")
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn issue(key: &str, issue_type: &str, components: &[&str]) -> ChangelogIssue {
+        ChangelogIssue {
+            key: String::from(key),
+            summary: String::from("Summary"),
+            issue_type: String::from(issue_type),
+            status: String::from("Done"),
+            components: components.iter().map(|c| c.to_string()).collect(),
+            fix_versions: vec![],
+            commits: vec![],
+        }
+    }
+
+    fn grouped(groups: &[Group]) -> Vec<(String, Vec<String>)> {
+        groups
+            .iter()
+            .map(|g| {
+                let keys = g.issues.iter().map(|i| i.key.clone()).collect();
+                (g.name.clone(), keys)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn group_by_type_and_component() {
+        let issues = vec![
+            issue("JB-1", "Bug", &["api", "cli"]),
+            issue("JB-2", "Story", &["cli"]),
+            issue("JB-3", "Bug", &[]),
+        ];
+        assert_eq!(
+            vec![
+                (
+                    String::from("Bug"),
+                    vec![String::from("JB-1"), String::from("JB-3")]
+                ),
+                (String::from("Story"), vec![String::from("JB-2")]),
+            ],
+            grouped(&Notes::group(issues.clone(), GroupBy::Type))
+        );
+        assert_eq!(
+            vec![
+                (String::from("No component"), vec![String::from("JB-3")]),
+                (String::from("api"), vec![String::from("JB-1")]),
+                (
+                    String::from("cli"),
+                    vec![String::from("JB-1"), String::from("JB-2")]
+                ),
+            ],
+            grouped(&Notes::group(issues, GroupBy::Component))
+        );
+    }
+}
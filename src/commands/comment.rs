@@ -1,8 +1,10 @@
-use crate::{config::Config, interactivity::issue_from_branch_or_prompt, repo::Repository};
+use crate::{
+    client::JiraClient, config::Config, interactivity::issue_from_branch_or_prompt,
+    repo::Repository,
+};
 use clap::Args;
 use color_eyre::eyre::{Result, WrapErr, eyre};
 use inquire::Text;
-use jira::JiraAPIClient;
 use jira::models::{IssueKey, PostCommentBody};
 
 use super::shared::ExecCommand;
@@ -18,7 +20,7 @@ pub struct Comment {
 
 impl ExecCommand for Comment {
     async fn exec(self, cfg: &Config) -> Result<String> {
-        let client = JiraAPIClient::new(&cfg.jira_cfg)?;
+        let client = JiraClient::new(cfg)?;
         let maybe_repo = Repository::open().wrap_err("Failed to open repository");
         let head = match maybe_repo {
             Ok(repo) => repo.get_branch_name()?,
@@ -0,0 +1,283 @@
+use crate::{
+    client::JiraClient,
+    config::{self, Config},
+    hooks::HookState,
+    repo::Repository,
+};
+use clap::Args;
+use color_eyre::eyre::{Report, Result, eyre};
+use color_eyre::owo_colors::OwoColorize;
+use reqwest::Certificate;
+use std::{env, fmt::Display, fs};
+
+#[derive(Args, Debug)]
+pub struct Doctor {}
+
+#[derive(Debug, PartialEq, Eq)]
+enum Status {
+    Pass,
+    Warn,
+    Fail,
+}
+
+#[derive(Debug)]
+struct Check {
+    name: &'static str,
+    status: Status,
+    detail: String,
+    suggestion: Option<String>,
+}
+
+impl Check {
+    fn pass(name: &'static str, detail: impl Into<String>) -> Self {
+        Check {
+            name,
+            status: Status::Pass,
+            detail: detail.into(),
+            suggestion: None,
+        }
+    }
+
+    fn warn(name: &'static str, detail: impl Into<String>, suggestion: impl Into<String>) -> Self {
+        Check {
+            name,
+            status: Status::Warn,
+            detail: detail.into(),
+            suggestion: Some(suggestion.into()),
+        }
+    }
+
+    fn fail(name: &'static str, detail: impl Into<String>, suggestion: impl Into<String>) -> Self {
+        Check {
+            name,
+            status: Status::Fail,
+            detail: detail.into(),
+            suggestion: Some(suggestion.into()),
+        }
+    }
+}
+
+impl Display for Check {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let label = match self.status {
+            Status::Pass => "pass".bright_green().to_string(),
+            Status::Warn => "warn".bright_yellow().to_string(),
+            Status::Fail => "fail".bright_red().to_string(),
+        };
+        write!(f, "[{label}] {}: {}", self.name, self.detail)?;
+        if let Some(suggestion) = &self.suggestion {
+            write!(f, "\n       {}", suggestion.dimmed())?;
+        }
+        Ok(())
+    }
+}
+
+impl Doctor {
+    pub async fn diagnose(self, cfg: Result<Config>) -> Result<String> {
+        let mut checks = Self::config_files();
+
+        match &cfg {
+            Ok(cfg) => {
+                checks.push(Check::pass("Config", "Loaded"));
+                checks.extend(Self::tls(cfg));
+                checks.push(Check::pass("Proxy", cfg.proxy_cfg.describe()));
+                checks.extend(Self::jira(cfg).await);
+            }
+            Err(e) => checks.push(Check::fail(
+                "Config",
+                format!("{e:#}"),
+                "Create or overwrite config with: jig init",
+            )),
+        }
+
+        checks.push(Self::commit_msg_hook());
+        checks.push(Self::browser());
+
+        for check in &checks {
+            println!("{check}");
+        }
+
+        let failed = checks.iter().filter(|c| c.status == Status::Fail).count();
+        if failed > 0 {
+            Err(eyre!("{failed} of {} checks failed", checks.len()))
+        } else {
+            Ok(String::default())
+        }
+    }
+
+    fn config_files() -> Vec<Check> {
+        [
+            ("Global config", config::config_file()),
+            ("Workspace config", config::workspace_config_file()),
+        ]
+        .into_iter()
+        .filter(|(_, path)| path.exists())
+        .map(|(name, path)| {
+            let content = fs::read_to_string(&path).map_err(|e| e.to_string());
+            match content.and_then(|c| c.parse::<toml::Table>().map_err(|e| e.to_string())) {
+                Ok(_) => Check::pass(name, path.to_string_lossy()),
+                Err(e) => Check::fail(
+                    name,
+                    format!("{}: {e}", path.to_string_lossy()),
+                    "Fix the TOML syntax or regenerate with: jig init",
+                ),
+            }
+        })
+        .collect()
+    }
+
+    fn tls(cfg: &Config) -> Vec<Check> {
+        let ca = match &cfg.jira_cfg.ca_certificate {
+            Some(pem) => match Certificate::from_pem_bundle(pem.as_bytes()) {
+                Ok(certs) => Check::pass("CA certificate", format!("{} loaded", certs.len())),
+                Err(e) => Check::fail(
+                    "CA certificate",
+                    e.to_string(),
+                    "Verify tls_ca_certificate_path contains PEM encoded certificates",
+                ),
+            },
+            None => Check::pass("CA certificate", "System trust store"),
+        };
+
+        let mut checks = vec![ca];
+        if cfg.jira_cfg.insecure_skip_tls_verify {
+            checks.push(Check::warn(
+                "TLS verification",
+                "Disabled",
+                "Prefer tls_ca_certificate_path over insecure_skip_tls_verify",
+            ));
+        }
+        if cfg.tls_client_identity.is_some() {
+            checks.push(Check::pass("Client certificate", "Loaded"));
+        }
+        checks
+    }
+
+    async fn jira(cfg: &Config) -> Vec<Check> {
+        let client = match JiraClient::new(cfg) {
+            Ok(client) => client,
+            Err(e) => {
+                return vec![Check::fail(
+                    "Jira client",
+                    format!("{e:#}"),
+                    "Verify jira_url and TLS settings",
+                )];
+            }
+        };
+
+        let mut checks = vec![];
+        match client.get_server_info().await {
+            Ok(info) => checks.push(Check::pass(
+                "Jira reachable",
+                format!(
+                    "{} (Jira {})",
+                    info.server_title.unwrap_or(client.url.to_string()),
+                    info.version
+                ),
+            )),
+            Err(e) => {
+                checks.push(Check::fail(
+                    "Jira reachable",
+                    format!("{}: {:#}", client.url, Report::new(e)),
+                    "Verify jira_url, network access, proxy and TLS settings",
+                ));
+                return checks;
+            }
+        }
+
+        match client.get_myself().await {
+            Ok(user) => checks.push(Check::pass(
+                "Authentication",
+                format!("Authenticated as {}", user.display_name),
+            )),
+            Err(e) => {
+                checks.push(Check::fail(
+                    "Authentication",
+                    format!("{:#}", Report::new(e)),
+                    "Create a new Personal Access Token and update pat_token",
+                ));
+                return checks;
+            }
+        }
+
+        match client.query_issues(&cfg.issue_query, None, None).await {
+            Ok(res) if res.issues.as_ref().is_some_and(|i| !i.is_empty()) => {
+                checks.push(Check::pass(
+                    "Issue query",
+                    format!("{} issues", res.total.unwrap_or_default()),
+                ))
+            }
+            Ok(_) => checks.push(Check::warn(
+                "Issue query",
+                "No issues found",
+                "Issue prompts will be empty, adjust issue_query",
+            )),
+            Err(e) => checks.push(Check::fail(
+                "Issue query",
+                format!("{:#}", Report::new(e)),
+                "Test the JQL in the Jira issue search and update issue_query",
+            )),
+        }
+
+        checks
+    }
+
+    fn commit_msg_hook() -> Check {
+        let name = "commit-msg hook";
+        let hooks_path = match Repository::open().and_then(|repo| repo.get_hooks_path()) {
+            Ok(path) => path,
+            Err(e) => {
+                return Check::warn(name, format!("{e:#}"), "Run jig doctor inside a repository");
+            }
+        };
+
+        let hook = hooks_path.join("commit-msg");
+        let hook_str = hook.to_string_lossy().to_string();
+        let state = HookState::of(&hook);
+        let state_broken = state.is_broken();
+        match state {
+            HookState::Jig => Check::pass(name, hook_str),
+            HookState::Shim if !state_broken => Check::pass(name, format!("{hook_str} (shim)")),
+            HookState::Shim => Check::fail(
+                name,
+                format!("Shim installed, but jig is not found in PATH: {hook_str}"),
+                "Commits will fail, add jig to PATH or reinstall with: jig hook install --force",
+            ),
+            HookState::Missing | HookState::Forwarder => Check::warn(
+                name,
+                format!("Not installed: {hook_str}"),
+                "Install with: jig hook install",
+            ),
+            HookState::Foreign => Check::warn(
+                name,
+                format!("Not managed by jig: {hook_str}"),
+                "Replace with: jig hook install --force, or keep it with --chain",
+            ),
+            HookState::OtherBinary(target) => Check::warn(
+                name,
+                format!("Points to another binary: {}", target.to_string_lossy()),
+                "Reinstall with: jig hook install --force",
+            ),
+            HookState::Broken(target) => Check::fail(
+                name,
+                format!("Broken symlink: {hook_str} -> {}", target.to_string_lossy()),
+                "Commits will fail, repair with: jig hook install --shim",
+            ),
+        }
+    }
+
+    fn browser() -> Check {
+        if cfg!(target_os = "windows") {
+            return Check::pass("Browser", "powershell.exe");
+        }
+
+        match env::var("BROWSER") {
+            Ok(browser) if !browser.trim().is_empty() => Check::pass("Browser", browser),
+            _ => Check::warn(
+                "Browser",
+                "$BROWSER is unset",
+                "jig open and jig init cannot open your browser, export BROWSER=<browser>",
+            ),
+        }
+    }
+}
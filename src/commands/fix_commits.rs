@@ -0,0 +1,193 @@
+use crate::{
+    config::Config,
+    hooks::CommitMsg,
+    interactivity::prompt_user_with_issue_key_select,
+    repo::{CommitInfo, Repository},
+};
+use clap::{Args, ValueHint};
+use color_eyre::{
+    Section,
+    eyre::{Result, WrapErr, eyre},
+};
+use inquire::Confirm;
+use jira::models::IssueKey;
+
+use super::shared::ExecCommand;
+
+/// Tried in order when no base branch is given, origin/HEAD is set by git clone
+const BASE_CANDIDATES: [&str; 3] = ["origin/HEAD", "main", "master"];
+
+#[derive(Args, Debug)]
+pub struct FixCommits {
+    /// Commits to rewrite, e.g. main..HEAD.
+    /// Defaults to the commits on the branch not in the base branch
+    #[arg(value_name = "RANGE", value_hint = ValueHint::Unknown)]
+    range: Option<String>,
+
+    /// Base branch of the default range, defaults to origin/HEAD, main or master
+    #[arg(short, long, conflicts_with = "range", value_hint = ValueHint::Unknown)]
+    base: Option<String>,
+
+    /// Rewrite commits that already exist on a remote branch
+    #[arg(short, long)]
+    force: bool,
+
+    /// Skip the confirmation prompt
+    #[arg(short, long)]
+    yes: bool,
+}
+
+/// Commit to copy, with its new message if it lacked an issue key
+struct Rewrite {
+    commit: CommitInfo,
+    message: Option<String>,
+}
+
+impl FixCommits {
+    fn default_base(repo: &Repository, branch: &str) -> Result<String> {
+        BASE_CANDIDATES
+            .into_iter()
+            .find(|base| *base != branch && repo.rev_parse(base).is_ok())
+            .map(String::from)
+            .ok_or(eyre!("Unable to find the base branch"))
+            .with_suggestion(|| "Specify it with: jig fix-commits --base <branch>")
+    }
+
+    fn preview(branch: &str, rewrites: &[Rewrite]) {
+        let fixed = rewrites.iter().filter(|r| r.message.is_some()).count();
+        println!("Adding issue keys to {fixed} commit(s) on {branch}:");
+        for rewrite in rewrites {
+            let Some(message) = &rewrite.message else {
+                continue;
+            };
+            println!(
+                "  {} {}\n  {:8} {}",
+                rewrite.commit.short_id(),
+                rewrite.commit.subject(),
+                "->",
+                message.lines().next().unwrap_or_default()
+            );
+        }
+    }
+}
+
+impl ExecCommand for FixCommits {
+    async fn exec(self, cfg: &Config) -> Result<String> {
+        let repo = Repository::open().wrap_err("Failed to open repository")?;
+        let branch = repo.get_branch_name()?;
+        let head = repo.rev_parse("HEAD")?;
+        if repo.rev_parse(&format!("refs/heads/{branch}")).is_err()
+            || ["rebase-merge", "rebase-apply"]
+                .iter()
+                .any(|state_file| repo.in_progress(state_file))
+        {
+            return Err(eyre!("HEAD is not on a branch"))
+                .with_suggestion(|| "Finish the rebase or checkout a branch first");
+        }
+
+        let (base, tip) = match &self.range {
+            Some(range) => {
+                let (base, tip) = Repository::split_range(range)?;
+                (base.to_string(), tip.to_string())
+            }
+            None => (
+                match self.base {
+                    Some(base) => base,
+                    None => Self::default_base(&repo, &branch)?,
+                },
+                String::from("HEAD"),
+            ),
+        };
+        if repo.rev_parse(&tip)? != head {
+            return Err(eyre!(
+                "Only commits on the checked out branch can be rewritten"
+            ))
+            .with_suggestion(|| format!("Checkout {tip} first"));
+        }
+        // Fail on typos instead of rewriting the entire history
+        let base = repo.rev_parse(&base)?;
+
+        let mut commits = repo.get_commits(&head, &[base])?;
+        if commits.iter().any(|commit| commit.parent_count > 1) {
+            return Err(eyre!("Range contains merge commits"))
+                .with_suggestion(|| "Reword the commits with: git rebase -i");
+        }
+        commits.reverse();
+
+        // Commits before the first one missing a key keep their id
+        let Some(first_fix) = commits.iter().position(|commit| {
+            CommitMsg::is_missing_issue_key(&commit.message, &cfg.hooks_cfg).unwrap_or(false)
+        }) else {
+            return Ok(String::from("All commits reference an issue"));
+        };
+        let commits = commits.split_off(first_fix);
+
+        let on_remote = {
+            let unpushed = repo
+                .get_commits(&head, &repo.get_all_remote_tips()?)?
+                .into_iter()
+                .map(|commit| commit.id)
+                .collect::<Vec<_>>();
+            commits
+                .iter()
+                .filter(|commit| !unpushed.contains(&commit.id))
+                .map(|commit| format!("  {} {}", commit.short_id(), commit.subject()))
+                .collect::<Vec<_>>()
+        };
+        if !on_remote.is_empty() && !self.force {
+            return Err(eyre!(
+                "Refusing to rewrite {} commit(s) already on a remote branch:\n{}",
+                on_remote.len(),
+                on_remote.join("\n")
+            ))
+            .with_suggestion(|| "Rewrite them anyway with: jig fix-commits --force")
+            .with_note(|| "Pushing rewritten commits requires: git push --force-with-lease");
+        }
+
+        let issue_key = match IssueKey::try_from(branch.clone()) {
+            Ok(issue_key) => issue_key,
+            Err(_) => prompt_user_with_issue_key_select(cfg).await?,
+        };
+
+        let mut rewrites = vec![];
+        for commit in commits {
+            let message = match CommitMsg::is_missing_issue_key(&commit.message, &cfg.hooks_cfg)? {
+                true => Some(
+                    CommitMsg::fix_message(&commit.message, &issue_key, cfg)
+                        .await
+                        .wrap_err(format!("Unable to fix commit {}", commit.short_id()))?,
+                ),
+                false => None,
+            };
+            rewrites.push(Rewrite { commit, message });
+        }
+
+        Self::preview(&branch, &rewrites);
+        if !self.yes
+            && !Confirm::new("Rewrite commits")
+                .with_default(true)
+                .with_help_message("--yes to skip this prompt")
+                .prompt()?
+        {
+            return Ok(String::from("No commits rewritten"));
+        }
+
+        // The parent of the first rewritten commit, None for the root commit
+        let mut parent = repo.rev_parse(&format!("{}^", rewrites[0].commit.id)).ok();
+        for rewrite in &rewrites {
+            let message = rewrite
+                .message
+                .as_deref()
+                .unwrap_or(&rewrite.commit.message);
+            parent = Some(repo.reword_commit(&rewrite.commit.id, parent.as_deref(), message)?);
+        }
+        let new_head = parent.expect("At least one commit is rewritten");
+        repo.update_branch(&branch, &new_head, &head, "jig fix-commits")?;
+
+        Ok(format!(
+            "Rewrote {} commit(s), undo with: git reset --keep {}",
+            rewrites.len(),
+            &head[..8]
+        ))
+    }
+}
@@ -1,10 +1,20 @@
-use crate::repo::Repository;
-use clap::Args;
+use crate::{
+    config::{self, Config},
+    hooks::{
+        self, CHAINED_SUFFIX, FORWARD_MARKER, GIT_HOOK_NAMES, GitHook, HOOK_NAMES,
+        HOOKS_PATH_BACKUP, HookState, SHIM_MARKER, forwarded_hooks, jig_in_path,
+    },
+    repo::Repository,
+};
+use clap::{Args, Subcommand, ValueEnum};
 use color_eyre::Section;
 use color_eyre::eyre::{Result, WrapErr, eyre};
 use inquire::Confirm;
-use std::env::{current_exe, var};
-use std::path::PathBuf;
+use std::{
+    env::current_exe,
+    fs,
+    path::{Path, PathBuf},
+};
 
 #[cfg(target_os = "linux")]
 use std::os::unix::fs::symlink;
@@ -13,56 +23,772 @@ use std::os::windows::fs::symlink_file;
 
 #[derive(Args, Debug)]
 pub struct Hooks {
-    /// Skip confirmation
+    #[command(subcommand)]
+    command: Option<HookCommand>,
+
+    /// Skip confirmation, without a subcommand the commit-msg hook is installed
+    #[arg(short, long)]
+    pub force: bool,
+}
+
+#[derive(Subcommand, Debug)]
+enum HookCommand {
+    /// Install git hooks, commit-msg by default
+    Install(Install),
+    /// Remove git hooks installed by jig, restoring chained hooks
+    Uninstall {
+        /// Defaults to all hooks supported by jig
+        #[arg(value_name = "HOOK", value_parser = HOOK_NAMES)]
+        names: Vec<String>,
+
+        /// Remove the global hooks, restoring the previous global core.hooksPath
+        #[arg(short, long)]
+        global: bool,
+    },
+    /// Show which hooks are installed
+    Status {
+        /// Report broken installs in all repositories under DIR instead
+        #[arg(long, value_name = "DIR")]
+        scan: Option<PathBuf>,
+    },
+    /// Run a hook, for hook managers calling commands instead of symlinks
+    Run {
+        #[arg(value_name = "HOOK", value_parser = HOOK_NAMES)]
+        name: String,
+        /// Arguments git passed to the hook
+        #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
+        args: Vec<String>,
+    },
+    /// Run the hooks of the repository, from the scripts installed by install --global
+    #[command(hide = true)]
+    Forward {
+        #[arg(value_name = "HOOK", value_parser = GIT_HOOK_NAMES)]
+        name: String,
+        /// Arguments git passed to the hook
+        #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
+        args: Vec<String>,
+    },
+    /// Print the configuration running jig from a hook manager
+    Snippet {
+        manager: HookManager,
+        #[arg(value_name = "HOOK", value_parser = HOOK_NAMES, default_value = "commit-msg")]
+        names: Vec<String>,
+    },
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum HookManager {
+    Lefthook,
+    Husky,
+    PreCommit,
+}
+
+#[derive(Args, Debug)]
+pub struct Install {
+    #[arg(value_name = "HOOK", value_parser = HOOK_NAMES, default_value = "commit-msg")]
+    pub names: Vec<String>,
+
+    /// Replace existing hooks without confirmation
     #[arg(short, long)]
     pub force: bool,
+
+    /// Keep existing hooks, renamed to <HOOK>.jig-chained and run before jig
+    #[arg(short, long, conflicts_with = "force")]
+    pub chain: bool,
+
+    /// Install a script running jig from PATH, surviving jig moving to another location
+    #[arg(short, long)]
+    pub shim: bool,
+
+    /// Install for all repositories through the global core.hooksPath.
+    /// Hooks in .git/hooks and the replaced core.hooksPath keep running, forwarded by jig
+    #[arg(short, long, conflicts_with = "chain")]
+    pub global: bool,
+}
+
+/// Directory levels searched for repositories by `jig hook status --scan`
+const SCAN_DEPTH: usize = 4;
+
+fn shim_script(name: &str) -> String {
+    format!("#!/bin/sh\n{SHIM_MARKER}\nexec jig hook run {name} \"$@\"\n")
+}
+
+/// Repositories in `dir` and its subdirectories, not descending into repositories
+fn find_repositories(dir: &Path, depth: usize, repos: &mut Vec<PathBuf>) {
+    // .git is a file in linked worktrees and submodules
+    if dir.join(".git").exists() {
+        repos.push(dir.to_path_buf());
+        return;
+    }
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    if depth == 0 {
+        return;
+    }
+
+    for entry in entries.flatten() {
+        let hidden = entry.file_name().to_string_lossy().starts_with('.');
+        // Symlinked directories are skipped to avoid cycles
+        if !hidden && entry.file_type().is_ok_and(|t| t.is_dir()) {
+            find_repositories(&entry.path(), depth - 1, repos);
+        }
+    }
+}
+
+fn forwarder_script(bin_path: &Path, name: &str) -> String {
+    format!(
+        "#!/bin/sh\n{FORWARD_MARKER}\nexec '{}' hook forward {name} \"$@\"\n",
+        bin_path.display()
+    )
+}
+
+/// Git ignores .git/hooks while the global hooks path is set, so every hook without a jig hook
+/// runs those of the repository instead. Returns the number of scripts written.
+fn write_forwarders(hooks_path: &Path) -> Result<usize> {
+    let bin_path = current_exe().wrap_err("Unable to obtain path of executable (jig)")?;
+    let mut written = 0;
+    for name in GIT_HOOK_NAMES {
+        let hook = hooks_path.join(name);
+        if HookState::of(&hook) != HookState::Missing {
+            continue;
+        }
+        fs::write(&hook, forwarder_script(&bin_path, name))
+            .wrap_err(format!("Unable to write {}", hook.display()))?;
+        Install::make_executable(&hook)?;
+        written += 1;
+    }
+    Ok(written)
+}
+
+fn remove_forwarders(hooks_path: &Path) -> Result<()> {
+    for name in GIT_HOOK_NAMES {
+        let hook = hooks_path.join(name);
+        if HookState::of(&hook) == HookState::Forwarder {
+            fs::remove_file(&hook).wrap_err(format!("Unable to remove {}", hook.display()))?;
+        }
+    }
+    Ok(())
+}
+
+fn chained_path(hook: &Path) -> PathBuf {
+    let mut chained = hook.as_os_str().to_owned();
+    chained.push(CHAINED_SUFFIX);
+    PathBuf::from(chained)
 }
 
 impl Hooks {
-    pub fn install(self) -> Result<String> {
-        let bin_path = current_exe().wrap_err("Unable to obtain path of executable (jig)")?;
+    pub async fn exec(self, cfg: Result<Config>) -> Result<String> {
+        match self.command {
+            Some(HookCommand::Install(install)) => install.exec(),
+            Some(HookCommand::Uninstall { names, global }) => Self::uninstall(names, global),
+            Some(HookCommand::Status { scan: None }) => Self::status(),
+            Some(HookCommand::Status { scan: Some(dir) }) => Self::scan(&dir),
+            Some(HookCommand::Run { name, args }) => {
+                let hook = GitHook::new(&name, &args)?
+                    .ok_or(eyre!("Hook '{name}' is not implemented by jig"))?;
+                hooks::run(hook, || cfg, &args).await;
+                Ok(String::default())
+            }
+            Some(HookCommand::Forward { name, args }) => {
+                // Every forwarded hook is given the input git passes
+                let input = match name.as_str() {
+                    "pre-push"
+                    | "reference-transaction"
+                    | "pre-receive"
+                    | "post-receive"
+                    | "post-rewrite" => Some(
+                        std::io::read_to_string(std::io::stdin())
+                            .wrap_err("Failed to read stdin")?,
+                    ),
+                    _ => None,
+                };
+                hooks::run_forwarded(&Repository::open()?, &name, input.as_deref(), &args)?;
+                Ok(String::default())
+            }
+            Some(HookCommand::Snippet { manager, names }) => Self::snippet(manager, &names),
+            None => Install::commit_msg(self.force, false).exec(),
+        }
+    }
+
+    fn snippet(manager: HookManager, names: &[String]) -> Result<String> {
+        let snippets = names
+            .iter()
+            .map(|name| match manager {
+                HookManager::Lefthook => Ok(Self::lefthook_snippet(name)),
+                HookManager::Husky => Ok(format!("# .husky/{name}\njig hook run {name} \"$@\"")),
+                HookManager::PreCommit => Self::pre_commit_snippet(name),
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(match manager {
+            HookManager::Lefthook => format!("# lefthook.yml\n{}", snippets.join("\n")),
+            HookManager::Husky => snippets.join("\n\n"),
+            HookManager::PreCommit => format!(
+                "# .pre-commit-config.yaml, install with: pre-commit install{}\nrepos:\n  - repo: local\n    hooks:\n{}",
+                names
+                    .iter()
+                    .map(|name| format!(" --hook-type {name}"))
+                    .collect::<String>(),
+                snippets.join("\n")
+            ),
+        })
+    }
+
+    fn lefthook_snippet(name: &str) -> String {
+        // Lefthook passes the arguments of git as {1}, {2}, ...
+        let arg_count = match name {
+            "prepare-commit-msg" | "post-checkout" => 3,
+            "pre-push" => 2,
+            _ => 1,
+        };
+        let args = (1..=arg_count)
+            .map(|i| format!(" {{{i}}}"))
+            .collect::<String>();
+        let options = match name {
+            "pre-push" | "reference-transaction" => "\n      use_stdin: true",
+            // Prompts for an issue or transition need the terminal
+            "commit-msg" | "post-merge" => "\n      interactive: true",
+            _ => "",
+        };
+
+        format!("{name}:\n  commands:\n    jig:\n      run: jig hook run {name}{args}{options}")
+    }
+
+    fn pre_commit_snippet(name: &str) -> Result<String> {
+        // pre-commit passes the message file, but not the arguments and stdin of other hooks
+        if !matches!(name, "commit-msg" | "prepare-commit-msg") {
+            return Err(eyre!("pre-commit cannot run the '{name}' hook of jig")).with_suggestion(
+                || format!("Install it next to pre-commit with: jig hook install --chain {name}"),
+            );
+        }
+
+        Ok(format!(
+            "      - id: jig-{name}\n        name: jig {name}\n        entry: jig hook run {name}\n        language: system\n        stages: [{name}]\n        always_run: true"
+        ))
+    }
+
+    fn uninstall(names: Vec<String>, global: bool) -> Result<String> {
+        let hooks_path = match global {
+            true => config::global_hooks_dir(),
+            false => Repository::open()?.get_hooks_path()?,
+        };
+        Self::uninstall_from(&hooks_path, names, global)
+    }
+
+    fn uninstall_from(hooks_path: &Path, names: Vec<String>, global: bool) -> Result<String> {
+        let names = match names.is_empty() {
+            true => HOOK_NAMES.map(String::from).to_vec(),
+            false => names,
+        };
+
+        let mut report = vec![];
+        for name in names {
+            let hook = hooks_path.join(&name);
+            match HookState::of(&hook) {
+                HookState::Missing | HookState::Forwarder => continue,
+                // Symlinks to moved or previous jig binaries also keep the chained hook aside
+                HookState::Jig
+                | HookState::Shim
+                | HookState::Broken(_)
+                | HookState::OtherBinary(_) => {
+                    fs::remove_file(&hook)
+                        .wrap_err(format!("Unable to remove {}", hook.display()))?;
+                }
+                state => {
+                    report.push(format!("Skipped '{name}', {state}"));
+                    continue;
+                }
+            }
+
+            let chained = chained_path(&hook);
+            match chained.exists() {
+                true => {
+                    fs::rename(&chained, &hook)
+                        .wrap_err(format!("Unable to restore {}", chained.display()))?;
+                    report.push(format!("Uninstalled '{name}', restored chained hook"));
+                }
+                false => report.push(format!("Uninstalled '{name}'")),
+            }
+        }
+
+        if global {
+            report.extend(Self::restore_global_hooks_path(hooks_path)?);
+        }
 
+        match report.is_empty() {
+            true => Ok(String::from("No hooks installed")),
+            false => Ok(report.join("\n")),
+        }
+    }
+
+    fn restore_global_hooks_path(hooks_path: &Path) -> Result<Option<String>> {
+        let current = Repository::get_global_config("core.hooksPath")?;
+        if current.as_deref() != Some(&hooks_path.to_string_lossy()) {
+            return Ok(None);
+        }
+        // Restoring would silently disable the remaining hooks
+        if HOOK_NAMES.iter().any(|name| {
+            matches!(
+                HookState::of(&hooks_path.join(name)),
+                HookState::Jig | HookState::Shim | HookState::OtherBinary(_)
+            )
+        }) {
+            // The uninstalled hooks run those of the repository again
+            write_forwarders(hooks_path)?;
+            return Ok(Some(format!(
+                "Kept global core.hooksPath, jig hooks remain in {}",
+                hooks_path.display()
+            )));
+        }
+
+        remove_forwarders(hooks_path)?;
+        let backup = Repository::get_global_config(HOOKS_PATH_BACKUP)?;
+        Repository::set_global_config("core.hooksPath", backup.as_deref())?;
+        Repository::set_global_config(HOOKS_PATH_BACKUP, None)?;
+        Ok(Some(match backup {
+            Some(backup) => format!("Restored global core.hooksPath to {backup}"),
+            None => String::from("Unset global core.hooksPath"),
+        }))
+    }
+
+    fn status() -> Result<String> {
         let repo = Repository::open()?;
-        let mut hooks_path = repo.get_hooks_path()?;
-        if hooks_path.starts_with("~") {
-            let expanded_path = match hooks_path.to_str() {
-                Some(p) => p.replace('~', &var("HOME")?),
-                None => Err(eyre!("Unable to parse hooks path"))?,
-            };
-            hooks_path = PathBuf::from(expanded_path);
-        }
-
-        hooks_path.push("commit-msg");
-        if hooks_path.exists() {
-            let replace_hook = match self.force {
-                true => true,
-                false => Confirm::new(
+        let hooks_path = repo.get_hooks_path()?;
+        // Hooks in .git/hooks are ignored by git when core.hooksPath points elsewhere
+        let shadowed_path =
+            Some(repo.default_hooks_path()).filter(|default| *default != hooks_path);
+
+        let global = hooks_path == config::global_hooks_dir();
+
+        let mut report = vec![match global {
+            true => format!("Hooks path: {} (global)", hooks_path.display()),
+            false => format!("Hooks path: {}", hooks_path.display()),
+        }];
+        for name in HOOK_NAMES {
+            let hook = hooks_path.join(name);
+            let mut line = format!("  {name:<20}{}", HookState::of(&hook));
+            if chained_path(&hook).exists() {
+                line.push_str(&format!(", chained: {name}{CHAINED_SUFFIX}"));
+            }
+            if global {
+                for forwarded in forwarded_hooks(&repo, name) {
+                    line.push_str(&format!(", forwarding to {}", forwarded.display()));
+                }
+            } else if let Some(shadowed) = &shadowed_path
+                && HookState::of(&shadowed.join(name)) != HookState::Missing
+            {
+                line.push_str(&format!(
+                    ", {} is shadowed by core.hooksPath",
+                    shadowed.join(name).display()
+                ));
+            }
+            report.push(line);
+        }
+        Ok(report.join("\n"))
+    }
+
+    fn scan(dir: &Path) -> Result<String> {
+        let mut repos = vec![];
+        find_repositories(dir, SCAN_DEPTH, &mut repos);
+        repos.sort();
+
+        let broken = repos
+            .iter()
+            .flat_map(|repo| {
+                // Unreadable repositories have no hooks git would run
+                let hooks_path = Repository::open_at(repo)
+                    .and_then(|repo| repo.get_hooks_path())
+                    .ok();
+                HOOK_NAMES.into_iter().filter_map(move |name| {
+                    let state = HookState::of(&hooks_path.as_ref()?.join(name));
+                    state
+                        .is_broken()
+                        .then(|| format!("  {} {name}: {state}", repo.display()))
+                })
+            })
+            .collect::<Vec<_>>();
+
+        let summary = format!(
+            "Scanned {} repositories, {} broken hook(s)",
+            repos.len(),
+            broken.len()
+        );
+        match broken.is_empty() {
+            true => Ok(summary),
+            false => Ok(format!(
+                "{summary}\n{}\nRepair in each repository with: jig hook install --shim <HOOK>...",
+                broken.join("\n")
+            )),
+        }
+    }
+}
+
+impl Install {
+    pub fn commit_msg(force: bool, chain: bool) -> Install {
+        Install {
+            names: vec![String::from("commit-msg")],
+            force,
+            chain,
+            shim: false,
+            global: false,
+        }
+    }
+
+    pub fn exec(self) -> Result<String> {
+        let hooks_path = match self.global {
+            true => {
+                let hooks_path = config::global_hooks_dir();
+                fs::create_dir_all(&hooks_path)
+                    .wrap_err(format!("Unable to create {}", hooks_path.display()))?;
+                hooks_path
+            }
+            false => Repository::open()?.get_hooks_path()?,
+        };
+        self.install_at(&hooks_path)
+    }
+
+    fn install_at(self, hooks_path: &Path) -> Result<String> {
+        let bin_path = current_exe().wrap_err("Unable to obtain path of executable (jig)")?;
+        let mut report = vec![];
+        for name in &self.names {
+            let hook = hooks_path.join(name);
+            let state = HookState::of(&hook);
+            match &state {
+                HookState::Jig if !self.shim => {
+                    report.push(format!("Hook '{name}' already installed"));
+                    continue;
+                }
+                HookState::Shim if self.shim && jig_in_path() => {
+                    report.push(format!("Hook '{name}' already installed"));
+                    continue;
+                }
+                HookState::Missing => (),
+                // Switching between symlink and shim, or repairing a link to a removed binary
+                HookState::Jig | HookState::Shim | HookState::Forwarder | HookState::Broken(_) => {
+                    fs::remove_file(&hook)
+                        .wrap_err(format!("Unable to remove {}", hook.display()))?;
+                }
+                HookState::Foreign if self.chain => {
+                    let chained = chained_path(&hook);
+                    if chained.exists() {
+                        return Err(eyre!("Chained hook already exists: {}", chained.display()))
+                            .with_suggestion(|| "Remove or rename it before installing");
+                    }
+                    fs::rename(&hook, &chained)
+                        .wrap_err(format!("Unable to rename {}", hook.display()))?;
+                }
+                state => {
+                    if !self.replace(&hook, state)? {
+                        report.push(format!("Skipped '{name}', {state}"));
+                        continue;
+                    }
+                    fs::remove_file(&hook)
+                        .wrap_err(format!("Unable to remove {}", hook.display()))?;
+                }
+            }
+
+            match self.shim {
+                true => Self::write_shim(name, &hook)?,
+                false => Self::link(&bin_path, &hook)?,
+            }
+            report.push(match (&state, chained_path(&hook).exists()) {
+                (HookState::Broken(target), _) => {
                     format!(
-                        "Hook already exists, replace: {}",
-                        hooks_path.clone().to_str().unwrap()
+                        "Repaired '{name}' hook, {} no longer exists",
+                        target.display()
                     )
-                    .as_str(),
-                )
-                .with_default(true)
-                .with_help_message("--force to skip this prompt")
-                .prompt()?,
-            };
-
-            if replace_hook {
-                std::fs::remove_file(hooks_path.clone())?;
+                }
+                (_, true) => format!("Installed '{name}' hook, chaining {name}{CHAINED_SUFFIX}"),
+                (_, false) => format!("Installed '{name}' hook"),
+            });
+        }
+        if self.global {
+            let forwarded = write_forwarders(hooks_path)?;
+            if forwarded > 0 {
+                report.push(format!(
+                    "Forwarding {forwarded} other hooks to those of the repository"
+                ));
             }
+            report.extend(Self::use_global_hooks_path(hooks_path)?);
+        }
+        if self.shim && !jig_in_path() {
+            report.push(String::from(
+                "Warning: jig is not found in PATH, hooks installed as shims will fail",
+            ));
+        }
+
+        Ok(report.join("\n"))
+    }
+
+    fn use_global_hooks_path(hooks_path: &Path) -> Result<Option<String>> {
+        let hooks_path = hooks_path.to_string_lossy();
+        let previous = Repository::get_global_config("core.hooksPath")?;
+        if previous.as_deref() == Some(&hooks_path) {
+            return Ok(None);
+        }
+
+        if let Some(previous) = &previous {
+            Repository::set_global_config(HOOKS_PATH_BACKUP, Some(previous))?;
+        }
+        Repository::set_global_config("core.hooksPath", Some(&hooks_path))?;
+        Ok(Some(match previous {
+            Some(previous) => format!(
+                "Set global core.hooksPath to {hooks_path}, replacing {previous} until: jig hook uninstall --global"
+            ),
+            None => format!("Set global core.hooksPath to {hooks_path}"),
+        }))
+    }
+
+    fn replace(&self, hook: &Path, state: &HookState) -> Result<bool> {
+        if self.force {
+            return Ok(true);
+        }
+        // Hooks that can not be chained are kept as well
+        if self.chain {
+            return Ok(false);
+        }
+
+        Ok(Confirm::new(
+            format!(
+                "Hook already exists ({state}), replace: {}",
+                hook.to_str().unwrap()
+            )
+            .as_str(),
+        )
+        .with_default(true)
+        .with_help_message("--force to skip this prompt, --chain to keep it")
+        .prompt()?)
+    }
+
+    fn write_shim(name: &str, hook: &Path) -> Result<()> {
+        fs::write(hook, shim_script(name))
+            .wrap_err(format!("Unable to write {}", hook.display()))?;
+        Self::make_executable(hook)
+    }
+
+    fn make_executable(hook: &Path) -> Result<()> {
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(hook, fs::Permissions::from_mode(0o755))
+                .wrap_err(format!("Unable to make {} executable", hook.display()))?;
         }
 
+        Ok(())
+    }
+
+    fn link(bin_path: &Path, hook: &Path) -> Result<()> {
         #[cfg(target_os = "linux")]
-        symlink(bin_path, hooks_path.clone())
+        symlink(bin_path, hook)
             .wrap_err("Unable to create symbolic link")
-            .with_note(|| format!("target: {}", hooks_path.to_str().unwrap()))?;
+            .with_note(|| format!("target: {}", hook.to_str().unwrap()))?;
 
         #[cfg(target_os = "windows")]
-        symlink_file(bin_path, hooks_path.clone())
+        symlink_file(bin_path, hook)
             .wrap_err("Unable to create symbolic link, try to run as administrator.")
-            .with_note(|| format!("target: {}", hooks_path.to_str().unwrap()))?;
+            .with_note(|| format!("target: {}", hook.to_str().unwrap()))?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::repo::testing::GLOBAL_CONFIG;
+    // Hooks run as shell scripts, symlinks stand in for binaries
+    #[cfg(unix)]
+    use crate::{
+        hooks::run_chained,
+        repo::testing::{commit, git, init_repo},
+    };
+    #[cfg(unix)]
+    use std::os::unix::fs::symlink;
+
+    #[cfg(unix)]
+    fn write_script(path: &Path, script: &str) {
+        use std::os::unix::fs::PermissionsExt;
+        fs::write(path, script).unwrap();
+        fs::set_permissions(path, fs::Permissions::from_mode(0o755)).unwrap();
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn chained_hook_round_trip() {
+        let dir = init_repo("chain-hooks");
+        let hooks_path = dir.join(".git/hooks");
+        let hook = hooks_path.join("commit-msg");
+        let script = "#!/bin/sh\necho \"$1\" >> \"$(dirname \"$0\")/ran\"\n";
+        write_script(&hook, script);
+
+        Install {
+            names: vec![String::from("commit-msg")],
+            force: false,
+            chain: true,
+            shim: false,
+            global: false,
+        }
+        .install_at(&hooks_path)
+        .unwrap();
+        assert_eq!(HookState::Jig, HookState::of(&hook));
+        assert_eq!(script, fs::read_to_string(chained_path(&hook)).unwrap());
+
+        let args = [String::from(".git/COMMIT_EDITMSG")];
+        run_chained(
+            &Repository::open_at(&dir).unwrap(),
+            "commit-msg",
+            None,
+            &args,
+        )
+        .unwrap();
+        assert_eq!(
+            ".git/COMMIT_EDITMSG\n",
+            fs::read_to_string(hooks_path.join("ran")).unwrap()
+        );
+
+        let report =
+            Hooks::uninstall_from(&hooks_path, vec![String::from("commit-msg")], false).unwrap();
+        assert_eq!("Uninstalled 'commit-msg', restored chained hook", report);
+        assert_eq!(HookState::Foreign, HookState::of(&hook));
+        assert_eq!(script, fs::read_to_string(&hook).unwrap());
+        assert!(!chained_path(&hook).exists());
+
+        // Left behind by a jig binary installed elsewhere
+        fs::rename(&hook, chained_path(&hook)).unwrap();
+        symlink("/bin/true", &hook).unwrap();
+        assert!(matches!(HookState::of(&hook), HookState::OtherBinary(_)));
+        // Not replaced by unattended installs
+        let report = Install::commit_msg(false, true)
+            .install_at(&hooks_path)
+            .unwrap();
+        assert!(report.starts_with("Skipped 'commit-msg', points to another binary"));
+        Hooks::uninstall_from(&hooks_path, vec![String::from("commit-msg")], false).unwrap();
+        assert_eq!(script, fs::read_to_string(&hook).unwrap());
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn scan_uses_hooks_path_of_each_repository() {
+        let main = init_repo("scan-hooks");
+        // Repositories are not descended into, scan the directory holding the others
+        let dir = main.with_extension("scan");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir(&dir).unwrap();
+        fs::rename(&main, dir.join("main")).unwrap();
+        let main = dir.join("main");
+        commit(&main, "Add readme", "Author <author@example.com>");
+        git(&main, &["worktree", "add", "-q", "../linked"]);
+        symlink("/removed/jig", main.join(".git/hooks/post-merge")).unwrap();
+
+        let repo = dir.join("repo");
+        fs::create_dir(&repo).unwrap();
+        git(&repo, &["init", "-q"]);
+        git(&repo, &["config", "core.hooksPath", "tools/hooks"]);
+        fs::create_dir_all(repo.join("tools/hooks")).unwrap();
+        symlink("/removed/jig", repo.join("tools/hooks/pre-push")).unwrap();
+        // Ignored by git while core.hooksPath is set
+        symlink("/removed/jig", repo.join(".git/hooks/commit-msg")).unwrap();
+
+        let report = Hooks::scan(&dir).unwrap();
+        assert!(report.starts_with("Scanned 3 repositories, 3 broken hook(s)\n"));
+        assert!(report.contains(&format!("{} post-merge", dir.join("linked").display())));
+        assert!(report.contains(&format!("{} pre-push", repo.display())));
+        assert!(!report.contains("commit-msg"));
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn global_hooks_path_restored_after_last_hook() {
+        let dir = std::env::temp_dir().join(format!("jig-global-hooks-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let hooks_path = dir.join("hooks");
+        fs::create_dir_all(&hooks_path).unwrap();
+        let global_config = dir.join("gitconfig");
+        fs::write(&global_config, "[core]\n\thooksPath = /previous/hooks\n").unwrap();
+        GLOBAL_CONFIG.set(Some(global_config));
+        let global = |key: &str| Repository::get_global_config(key).unwrap();
+        let installed = Some(hooks_path.to_string_lossy().to_string());
+
+        Install {
+            names: vec![String::from("commit-msg"), String::from("pre-push")],
+            force: false,
+            chain: false,
+            shim: false,
+            global: true,
+        }
+        .install_at(&hooks_path)
+        .unwrap();
+        assert_eq!(installed, global("core.hooksPath"));
+        assert_eq!(
+            Some(String::from("/previous/hooks")),
+            global(HOOKS_PATH_BACKUP)
+        );
+        assert_eq!(
+            HookState::Forwarder,
+            HookState::of(&hooks_path.join("pre-commit"))
+        );
+
+        let report =
+            Hooks::uninstall_from(&hooks_path, vec![String::from("commit-msg")], true).unwrap();
+        assert!(report.ends_with(&format!("jig hooks remain in {}", hooks_path.display())));
+        assert_eq!(installed, global("core.hooksPath"));
+        assert_eq!(
+            HookState::Forwarder,
+            HookState::of(&hooks_path.join("commit-msg"))
+        );
+
+        Hooks::uninstall_from(&hooks_path, vec![], true).unwrap();
+        assert_eq!(
+            Some(String::from("/previous/hooks")),
+            global("core.hooksPath")
+        );
+        assert_eq!(None, global(HOOKS_PATH_BACKUP));
+        assert_eq!(
+            HookState::Missing,
+            HookState::of(&hooks_path.join("pre-commit"))
+        );
+
+        GLOBAL_CONFIG.set(None);
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn repository_hooks_forwarded() {
+        let dir = init_repo("forward-hooks");
+        write_script(
+            &dir.join(".git/hooks/pre-commit"),
+            "#!/bin/sh\necho \"$@\" >> \"$(dirname \"$0\")/ran\"\n",
+        );
+        let script = forwarder_script(Path::new("/opt/jig"), "pre-commit");
+        assert!(script.contains(FORWARD_MARKER));
+        assert!(script.ends_with("exec '/opt/jig' hook forward pre-commit \"$@\"\n"));
+
+        let args = [String::from("--arg")];
+        hooks::run_forwarded(
+            &Repository::open_at(&dir).unwrap(),
+            "pre-commit",
+            None,
+            &args,
+        )
+        .unwrap();
+        assert_eq!(
+            "--arg\n",
+            fs::read_to_string(dir.join(".git/hooks/ran")).unwrap()
+        );
+
+        let _ = fs::remove_dir_all(&dir);
+    }
 
-        Ok(String::from("Installed 'commit-msg' hook"))
+    #[test]
+    fn shim_runs_hook_from_path() {
+        let script = shim_script("pre-push");
+        assert!(script.starts_with("#!/bin/sh\n"));
+        assert!(script.contains(SHIM_MARKER));
+        assert!(script.ends_with("exec jig hook run pre-push \"$@\"\n"));
     }
 }
@@ -1,17 +1,50 @@
-use crate::config::{self, GitHooksRawConfig, RawConfig};
+use crate::client::JiraClient;
+use crate::config::{self, Config, GitHooksRawConfig, RawConfig};
 use clap::Args;
-use color_eyre::eyre::{Result, WrapErr, eyre};
+use color_eyre::Section;
+use color_eyre::eyre::{Report, Result, WrapErr, eyre};
 use color_eyre::owo_colors::OwoColorize;
 use inquire::{Confirm, CustomType, Password, Select, Text};
+use jira::JiraClientError;
 use std::{env, fmt::Display, fs, path::PathBuf, process::Command};
 use url::Url;
 
-use super::Hooks;
+use super::Install;
 
 #[derive(Args, Debug)]
 pub struct InitConfig {
+    /// Prompt for every option
     #[arg(short, long)]
     all: bool,
+
+    /// Jira url, skips the url prompt
+    #[arg(long, value_name = "URL")]
+    url: Option<String>,
+
+    /// Read the Personal Access Token from this environment variable
+    #[arg(long, value_name = "VAR")]
+    token_env: Option<String>,
+
+    /// Issue query, skips the issue query prompt
+    #[arg(long, value_name = "JQL")]
+    issue_query: Option<String>,
+
+    /// Save to the global config
+    #[arg(long, conflicts_with = "workspace")]
+    global: bool,
+
+    /// Save to the workspace config
+    #[arg(long)]
+    workspace: bool,
+
+    /// Install the commit-msg hook without prompting
+    #[arg(long)]
+    install_hook: bool,
+
+    /// Never prompt, accept defaults and existing values.
+    /// Fails if jira url or token are missing
+    #[arg(short, long)]
+    yes: bool,
 }
 
 struct PathPrompt {
@@ -26,106 +59,106 @@ impl Display for PathPrompt {
 }
 
 impl InitConfig {
-    pub fn init(&self) -> Result<String> {
-        let global_config = config::config_file();
-        let local_config = config::workspace_config_file();
-        let config_file_input = Select::new(
-            "Where to save config",
-            vec![
-                PathPrompt {
-                    name: String::from("Global"),
-                    path: global_config,
-                },
-                PathPrompt {
-                    name: String::from("Local"),
-                    path: local_config,
-                },
-            ],
-        )
-        .prompt()?;
-        let config_file = if config_file_input.to_string().starts_with("Global") {
-            config::config_file()
-        } else {
-            config::workspace_config_file()
-        };
+    pub async fn init(&self) -> Result<String> {
+        let config_file = self.config_file()?;
+        let existing_cfg = InitConfig::read_existing(&config_file)?;
+        let mut new_cfg = InitConfig::defaults(existing_cfg.as_ref())?;
 
-        let jira_url = InitConfig::jira_url()?;
-        let mut new_git_hooks = GitHooksRawConfig {
-            allow_branch_missing_issue_key: Some(false),
-            allow_branch_and_commit_msg_mismatch: Some(false),
-        };
-        let mut new_cfg = RawConfig {
-            jira_url,
-            pat_token: None,
-            jira_timeout_seconds: Some(10),
-            insecure_skip_tls_verify: Some(false),
-            tls_ca_certificate_path: None,
-            issue_query: String::from("assignee = currentUser() ORDER BY updated DESC"),
-            max_query_results: Some(100),
-            enable_comment_prompts: Some(false),
-            one_transition_auto_move: Some(false),
-            git_hooks: Some(new_git_hooks.clone()),
+        new_cfg.jira_url = match (&self.url, self.yes) {
+            (Some(url), _) => InitConfig::parse_url(url)?,
+            (None, true) if !new_cfg.jira_url.is_empty() => new_cfg.jira_url.clone(),
+            (None, true) => Err(eyre!("Missing jira url")).with_suggestion(|| "Supply --url")?,
+            (None, false) => InitConfig::jira_url(&new_cfg.jira_url)?,
         };
+        if let Some(issue_query) = &self.issue_query {
+            new_cfg.issue_query = issue_query.clone();
+        }
 
-        InitConfig::set_credentials(&mut new_cfg)?;
+        self.set_credentials(&mut new_cfg)?;
+        let client = self.verify_credentials(&mut new_cfg).await?;
 
-        if Confirm::new("Install Git hook commit-msg")
-            .with_help_message(
-                "Prefixes commits with issue key from branch name and prevents commits without an issue key",
-            )
-            .with_default(true)
-            .prompt()?
-        {
-            match Hooks::install(Hooks { force: false }) {
-                Ok(_) => (),
+        let install_hook = self.install_hook
+            || (!self.yes
+                && Confirm::new("Install Git hook commit-msg")
+                    .with_help_message(
+                        "Prefixes commits with issue key from branch name and prevents commits without an issue key",
+                    )
+                    .with_default(true)
+                    .prompt()?);
+        if install_hook {
+            // Unattended installs keep existing hooks instead of replacing them
+            match Install::commit_msg(false, self.yes).exec() {
+                Ok(report) => println!("{report}"),
                 Err(e) => {
-                    eprintln!("{}", format!("Failed to install hook with error: {e}\ncd to repository and install with:\njig hook\n").bright_red());
+                    eprintln!("{}", format!("Failed to install hook with error: {e}\ncd to repository and install with:\njig hook install\n").bright_red());
                     println!()
                 }
             }
         }
 
-        if !self.all {
-            return InitConfig::write_config(&new_cfg, config_file)
+        if !self.all || self.yes {
+            if let Some(client) = &client {
+                let valid = InitConfig::verify_issue_query(client, &new_cfg.issue_query).await;
+                if !valid && self.yes {
+                    Err(eyre!("Invalid issue query")).with_suggestion(|| "Supply --issue-query")?
+                }
+            }
+            return InitConfig::write_config(&new_cfg, existing_cfg, config_file)
                 .wrap_err("Failed to write partial config");
         }
 
         // Text prompts
-        new_cfg.issue_query = Text::new("Issue query")
-            .with_default(&new_cfg.issue_query)
-            .with_help_message(
-                "Try using existing filters: 'filter=<filterID> OR filter=<filterID>'",
-            )
-            .prompt()?;
+        loop {
+            new_cfg.issue_query = Text::new("Issue query")
+                .with_default(&new_cfg.issue_query)
+                .with_help_message(
+                    "Try using existing filters: 'filter=<filterID> OR filter=<filterID>'",
+                )
+                .prompt()?;
+
+            let Some(client) = &client else { break };
+            if InitConfig::verify_issue_query(client, &new_cfg.issue_query).await
+                || !Confirm::new("Edit issue query")
+                    .with_default(true)
+                    .prompt()?
+            {
+                break;
+            }
+        }
         new_cfg.max_query_results = Some(
             CustomType::<u32>::new("Maximum query results")
                 .with_help_message("Lower is faster in case of large queries (max 1500)")
-                .with_default(new_cfg.max_query_results.unwrap())
+                .with_default(new_cfg.max_query_results.unwrap_or(100))
                 .prompt()?,
         );
         new_cfg.jira_timeout_seconds = Some(
             CustomType::new("Rest Timeout (Seconds)")
-                .with_default(10u64)
+                .with_default(new_cfg.jira_timeout_seconds.unwrap_or(10))
                 .with_help_message("How long to wait on server to respond")
                 .prompt()?,
         );
 
         new_cfg.enable_comment_prompts = Some(
             Confirm::new("Always prompt for comments (Worklog)")
-                .with_default(new_cfg.enable_comment_prompts.unwrap())
+                .with_default(new_cfg.enable_comment_prompts.unwrap_or(false))
                 .with_help_message("Override with 'log -c \"\"'")
                 .prompt()?,
         );
         new_cfg.one_transition_auto_move = Some(
             Confirm::new("Automatically pick if there is only one option")
-                .with_default(new_cfg.one_transition_auto_move.unwrap())
+                .with_default(new_cfg.one_transition_auto_move.unwrap_or(false))
                 .prompt()?,
         );
 
+        let mut new_git_hooks = new_cfg.git_hooks.clone().unwrap_or_default();
         new_git_hooks.allow_branch_missing_issue_key = Some(
             Confirm::new("Githook: Skip 'branch is missing Issue Key' checks")
                 .with_help_message("Prompts with issue select when branch is missing an Issue key")
-                .with_default(false)
+                .with_default(
+                    new_git_hooks
+                        .allow_branch_missing_issue_key
+                        .unwrap_or(false),
+                )
                 .prompt()?,
         );
         new_git_hooks.allow_branch_and_commit_msg_mismatch = Some(
@@ -133,17 +166,100 @@ impl InitConfig {
                 "Githook: Skip 'branch and commit message issue keys do not match' checks",
             )
             .with_help_message("Allows overriding issue keys in commits without '--no-verify'")
-            .with_default(false)
+            .with_default(
+                new_git_hooks
+                    .allow_branch_and_commit_msg_mismatch
+                    .unwrap_or(false),
+            )
             .prompt()?,
         );
 
         new_cfg.git_hooks = Some(new_git_hooks);
 
-        InitConfig::write_config(&new_cfg, config_file).wrap_err("Failed to write full config")
+        InitConfig::write_config(&new_cfg, existing_cfg, config_file)
+            .wrap_err("Failed to write full config")
+    }
+
+    fn config_file(&self) -> Result<PathBuf> {
+        if self.global || (self.yes && !self.workspace) {
+            return Ok(config::config_file());
+        } else if self.workspace {
+            return Ok(config::workspace_config_file());
+        }
+
+        let config_file_input = Select::new(
+            "Where to save config",
+            vec![
+                PathPrompt {
+                    name: String::from("Global"),
+                    path: config::config_file(),
+                },
+                PathPrompt {
+                    name: String::from("Local"),
+                    path: config::workspace_config_file(),
+                },
+            ],
+        )
+        .prompt()?;
+
+        Ok(config_file_input.path)
+    }
+
+    fn read_existing(path: &PathBuf) -> Result<Option<toml::Value>> {
+        if !path.exists() {
+            return Ok(None);
+        }
+
+        let content = fs::read_to_string(path).wrap_err("Failed to read existing config")?;
+        let value = toml::from_str(&content)
+            .wrap_err("Existing config is not valid TOML")
+            .with_note(|| format!("path: {}", path.to_string_lossy()))?;
+        Ok(Some(value))
     }
 
-    fn write_config(cfg: &RawConfig, path: PathBuf) -> Result<String> {
-        let str_cfg = toml::to_string(&cfg).wrap_err("Failed to serialize new Config file")?;
+    /// Defaults overridden by any values in the existing config.
+    fn defaults(existing_cfg: Option<&toml::Value>) -> Result<RawConfig> {
+        let defaults = RawConfig {
+            jira_timeout_seconds: Some(10),
+            insecure_skip_tls_verify: Some(false),
+            issue_query: String::from("assignee = currentUser() ORDER BY updated DESC"),
+            max_query_results: Some(100),
+            enable_comment_prompts: Some(false),
+            one_transition_auto_move: Some(false),
+            git_hooks: Some(GitHooksRawConfig {
+                allow_branch_missing_issue_key: Some(false),
+                allow_branch_and_commit_msg_mismatch: Some(false),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+
+        let Some(existing_cfg) = existing_cfg else {
+            return Ok(defaults);
+        };
+
+        let defaults = toml::Value::try_from(defaults).wrap_err("Failed to serialize defaults")?;
+        config::merge_toml_values(defaults, existing_cfg.clone(), 3)
+            .try_into::<RawConfig>()
+            .wrap_err("Existing config contains invalid values")
+            .with_suggestion(|| "Fix or remove the existing config before running jig init")
+    }
+
+    fn write_config(
+        cfg: &RawConfig,
+        existing_cfg: Option<toml::Value>,
+        path: PathBuf,
+    ) -> Result<String> {
+        let new_cfg = toml::Value::try_from(cfg).wrap_err("Failed to serialize new Config file")?;
+        let (merged_cfg, action) = match existing_cfg {
+            Some(existing_cfg) => (
+                config::merge_toml_values(existing_cfg, new_cfg, 3),
+                "Updated",
+            ),
+            None => (new_cfg, "Wrote"),
+        };
+        let str_cfg =
+            toml::to_string(&merged_cfg).wrap_err("Failed to serialize new Config file")?;
 
         let dir = match path.parent() {
             Some(parent_dir) => parent_dir,
@@ -154,33 +270,60 @@ impl InitConfig {
         fs::write(path.clone().into_os_string(), str_cfg)
             .wrap_err("Failed to write config file to")?;
 
-        Ok(format!("Overwrote config: {}", path.to_str().unwrap()))
+        Ok(format!("{action} config: {}", path.to_str().unwrap()))
     }
 
-    fn jira_url() -> Result<String> {
-        let url_input = Text::new("jira_url")
-            .with_help_message("Type or paste any url/FQDN. Scheme defaults to HTTPS if missing")
-            .prompt()?;
+    fn jira_url(default: &str) -> Result<String> {
+        let mut prompt = Text::new("jira_url")
+            .with_help_message("Type or paste any url/FQDN. Scheme defaults to HTTPS if missing");
+        if !default.is_empty() {
+            prompt = prompt.with_default(default);
+        }
+
+        InitConfig::parse_url(&prompt.prompt()?)
+    }
 
+    fn parse_url(url_input: &str) -> Result<String> {
         let parsed_url = if !url_input.starts_with("http") {
             Url::parse(&format!("https://{url_input}")).wrap_err("Unable to parse url")?
         } else {
-            Url::parse(&url_input).wrap_err("Unable to parse url")?
+            Url::parse(url_input).wrap_err("Unable to parse url")?
         };
 
-        Ok(format!(
-            "{}://{}",
-            parsed_url.scheme(),
-            parsed_url.host_str().unwrap()
-        ))
+        match (parsed_url.host_str(), parsed_url.port()) {
+            (Some(host), Some(port)) => Ok(format!("{}://{host}:{port}", parsed_url.scheme())),
+            (Some(host), None) => Ok(format!("{}://{host}", parsed_url.scheme())),
+            (None, _) => Err(eyre!("Url is missing a host: {url_input}")),
+        }
     }
 
-    fn set_credentials(icfg: &mut RawConfig) -> Result<()> {
-        let auth_url = {
-            let url = format!("{}/secure/ViewProfile.jspa", icfg.jira_url);
-            icfg.pat_token = Some(String::default());
-            url
-        };
+    fn set_credentials(&self, icfg: &mut RawConfig) -> Result<()> {
+        if let Some(var) = &self.token_env {
+            let token = env::var(var)
+                .ok()
+                .filter(|t| !t.trim().is_empty())
+                .ok_or(eyre!("Environment variable '{var}' is unset or empty"))?;
+            icfg.pat_token = Some(token);
+            return Ok(());
+        }
+
+        let has_token = icfg.pat_token.as_ref().is_some_and(|t| !t.is_empty());
+        match (has_token, self.yes) {
+            (true, true) => return Ok(()),
+            (false, true) => {
+                Err(eyre!("Missing auth token")).with_suggestion(|| "Supply --token-env")?
+            }
+            (true, false)
+                if !Confirm::new("Replace existing auth token")
+                    .with_default(false)
+                    .prompt()? =>
+            {
+                return Ok(());
+            }
+            _ => (),
+        }
+
+        let auth_url = format!("{}/secure/ViewProfile.jspa", icfg.jira_url);
 
         let (browser, args) = match cfg!(target_os = "windows") {
             false => (env::var("BROWSER"), vec![auth_url.to_string()]),
@@ -201,14 +344,92 @@ impl InitConfig {
             }
         }
 
-        let token = Password::new("Auth token")
+        icfg.pat_token = Some(InitConfig::token()?);
+
+        Ok(())
+    }
+
+    fn token() -> Result<String> {
+        Password::new("Auth token")
             .without_confirmation()
             .with_display_mode(inquire::PasswordDisplayMode::Masked)
             .prompt()
-            .wrap_err("Missing authentication token input")?;
+            .wrap_err("Missing authentication token input")
+    }
 
-        icfg.pat_token = Some(token);
+    /// Confirm the server is reachable and the token authenticates, re-prompting for the token on 401.
+    /// Returns None if the user chose to continue without a verified connection.
+    async fn verify_credentials(&self, icfg: &mut RawConfig) -> Result<Option<JiraClient>> {
+        loop {
+            let client = JiraClient::new(&Config::try_from(icfg.clone())?)?;
 
-        Ok(())
+            let result = match client.get_server_info().await {
+                Ok(info) => client.get_myself().await.map(|user| (info, user)),
+                Err(e) => Err(e),
+            };
+
+            match result {
+                Ok((info, user)) => {
+                    println!(
+                        "Authenticated as {} on Jira {}",
+                        user.display_name.bright_green(),
+                        info.version
+                    );
+                    return Ok(Some(client));
+                }
+                Err(JiraClientError::JiraQueryAuthenticationError()) if self.yes => {
+                    return Err(eyre!("Authentication failed"))
+                        .with_suggestion(|| "Verify the token supplied with --token-env");
+                }
+                Err(JiraClientError::JiraQueryAuthenticationError()) => {
+                    eprintln!("{}", "Authentication failed, please try again".bright_red());
+                    icfg.pat_token = Some(InitConfig::token()?);
+                }
+                Err(e) => {
+                    eprintln!(
+                        "{}",
+                        format!("Unable to reach {}: {:#}", icfg.jira_url, Report::new(e))
+                            .bright_red()
+                    );
+                    let skip = !self.yes
+                        && Confirm::new("Continue without verifying credentials")
+                            .with_help_message("Verify your setup later with: jig doctor")
+                            .with_default(false)
+                            .prompt()?;
+                    if skip {
+                        return Ok(None);
+                    }
+                    return Err(eyre!("Unable to verify credentials"));
+                }
+            }
+        }
+    }
+
+    /// Run the issue query and report the number of results, returns false on failure.
+    async fn verify_issue_query(client: &JiraClient, query: &str) -> bool {
+        match client
+            .query_issues(query, Some(vec![String::from("summary")]), None)
+            .await
+        {
+            Ok(res) => {
+                let total = res.total.unwrap_or_default();
+                println!("Issue query returned {total} issues");
+                if total == 0 {
+                    eprintln!(
+                        "{}",
+                        "Issue prompts will be empty until the query matches issues"
+                            .bright_yellow()
+                    );
+                }
+                true
+            }
+            Err(e) => {
+                eprintln!(
+                    "{}",
+                    format!("Issue query failed: {:#}", Report::new(e)).bright_red()
+                );
+                false
+            }
+        }
     }
 }
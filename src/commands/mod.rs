@@ -1,27 +1,37 @@
 mod assign;
 mod branch;
+mod changelog;
 mod comment;
 mod completion;
+mod doctor;
+mod fix_commits;
 mod hooks;
 mod init_config;
 mod open;
 mod print_configs;
 mod query;
+mod release;
 mod transition;
 mod upgrade;
+mod verify;
 mod worklog;
 
 pub mod shared;
 
 pub use assign::Assign;
 pub use branch::Branch;
+pub use changelog::Changelog;
 pub use comment::Comment;
 pub use completion::Completion;
-pub use hooks::Hooks;
+pub use doctor::Doctor;
+pub use fix_commits::FixCommits;
+pub use hooks::{Hooks, Install};
 pub use init_config::InitConfig;
 pub use open::Open;
 pub use print_configs::PrintConfigs;
 pub use query::Query;
+pub use release::Release;
 pub use transition::Transition;
 pub use upgrade::Upgrade;
+pub use verify::Verify;
 pub use worklog::Worklog;
@@ -1,7 +1,10 @@
-use crate::{config::Config, interactivity::issue_from_branch_or_prompt, repo::Repository};
+use crate::{
+    client::JiraClient, config::Config, interactivity::issue_from_branch_or_prompt,
+    repo::Repository,
+};
 use clap::Args;
 use color_eyre::eyre::{Result, WrapErr};
-use jira::{JiraAPIClient, models::IssueKey};
+use jira::models::IssueKey;
 use std::{env, process::Command};
 
 use super::shared::ExecCommand;
@@ -13,7 +16,7 @@ pub struct Open {
 }
 
 impl Open {
-    pub fn open_issue(client: &JiraAPIClient, issue_key: IssueKey) -> Result<String> {
+    pub fn open_issue(client: &JiraClient, issue_key: IssueKey) -> Result<String> {
         let url = client.url.join(format!("/browse/{issue_key}").as_str())?;
         let (browser, args) = match cfg!(target_os = "windows") {
             false => (
@@ -35,7 +38,7 @@ impl Open {
 
 impl ExecCommand for Open {
     async fn exec(self, cfg: &Config) -> Result<String> {
-        let client = JiraAPIClient::new(&cfg.jira_cfg)?;
+        let client = JiraClient::new(cfg)?;
 
         let maybe_repo = Repository::open().wrap_err("Failed to open repository");
         let head = match maybe_repo {
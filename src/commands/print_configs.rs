@@ -8,7 +8,7 @@ use super::shared::ExecCommand;
 pub struct PrintConfigs {}
 
 impl ExecCommand for PrintConfigs {
-    async fn exec(self, _cfg: &Config) -> Result<String> {
+    async fn exec(self, cfg: &Config) -> Result<String> {
         if config::config_file().exists() {
             println!("Global: {:?}", config::config_file());
         }
@@ -23,6 +23,8 @@ impl ExecCommand for PrintConfigs {
                 config::workspace_config_file()
             ))?
         }
+
+        println!("Proxy: {}", cfg.proxy_cfg.describe());
         Ok(String::default())
     }
 }
@@ -1,12 +1,9 @@
 use std::collections::HashMap;
 
-use crate::config::Config;
+use crate::{client::JiraClient, config::Config};
 use clap::Args;
 use color_eyre::eyre::{Context, Result};
-use jira::{
-    JiraAPIClient,
-    models::{Issue, PostIssueQueryResponseBody},
-};
+use jira::models::{Issue, PostIssueQueryResponseBody};
 use serde::Serialize;
 use serde_json::Value as JsonValue;
 
@@ -38,13 +35,11 @@ pub struct Query {
 impl ExecCommand for Query {
     async fn exec(self, cfg: &Config) -> Result<String> {
         let client = match self.count {
-            None => {
-                JiraAPIClient::new(&cfg.jira_cfg).with_context(|| "Failed to construct API client")
-            }
+            None => JiraClient::new(cfg).with_context(|| "Failed to construct API client"),
             Some(count) => {
-                let mut jira_cfg = cfg.jira_cfg.to_owned();
-                jira_cfg.max_query_results = count;
-                JiraAPIClient::new(&jira_cfg)
+                let mut cfg = cfg.to_owned();
+                cfg.jira_cfg.max_query_results = count;
+                JiraClient::new(&cfg)
                     .with_context(|| "Failed to construct API client with override")
             }
         }?;
@@ -0,0 +1,260 @@
+use crate::{
+    client::JiraClient,
+    commands::Transition,
+    config::Config,
+    hooks::CommitMsg,
+    repo::{CommitInfo, Repository},
+};
+use clap::{Args, ValueHint};
+use color_eyre::{
+    Section,
+    eyre::{Result, WrapErr, eyre},
+};
+use jira::models::{Issue, IssueKey};
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+use super::shared::ExecCommand;
+
+#[derive(Args, Debug)]
+pub struct Release {
+    /// Commits to collect issues from, e.g. v1.3.0..v1.4.0
+    #[arg(value_name = "RANGE", value_hint = ValueHint::Unknown)]
+    range: String,
+
+    /// Fix version to add to each issue, created in the project when missing
+    #[arg(short, long, value_hint = ValueHint::Unknown)]
+    version: String,
+
+    /// Transition to run on each issue, e.g. Released
+    #[arg(short, long, value_hint = ValueHint::Unknown)]
+    transition: Option<String>,
+
+    /// Report what would be done without changing Jira
+    #[arg(short = 'n', long)]
+    dry_run: bool,
+}
+
+/// Outcome of releasing a single issue
+#[derive(Debug)]
+struct IssueReport {
+    key: IssueKey,
+    done: Vec<String>,
+    error: Option<String>,
+}
+
+impl IssueReport {
+    fn line(&self) -> String {
+        match &self.error {
+            None => format!("  {} ok: {}", self.key, self.done.join(", ")),
+            Some(error) if self.done.is_empty() => format!("  {} FAILED: {error}", self.key),
+            Some(error) => format!(
+                "  {} FAILED: {error} (after: {})",
+                self.key,
+                self.done.join(", ")
+            ),
+        }
+    }
+}
+
+/// Project key of the issue, e.g. JB for JB-12
+fn project(issue_key: &IssueKey) -> String {
+    let key = issue_key.to_string();
+    key.rsplit_once('-')
+        .map(|(project, _)| project.to_string())
+        .unwrap_or(key)
+}
+
+fn has_fix_version(issue: &Issue, version: &str) -> bool {
+    match issue.fields.customfields.get("fixVersions") {
+        Some(Value::Array(versions)) => versions
+            .iter()
+            .any(|v| v.get("name").and_then(Value::as_str) == Some(version)),
+        _ => false,
+    }
+}
+
+impl Release {
+    /// Issue keys referenced in the commits, oldest first
+    fn issue_keys(commits: &[CommitInfo], cfg: &Config) -> Vec<IssueKey> {
+        let mut keys = vec![];
+        for commit in commits.iter().rev() {
+            if let Some(key) =
+                CommitMsg::referenced_issue_key(commit, cfg.hooks_cfg.reference_style)
+                && !keys.contains(&key)
+            {
+                keys.push(key);
+            }
+        }
+        keys
+    }
+
+    /// Make sure the version exists in the project, returns what was done
+    async fn ensure_version(&self, client: &JiraClient, project: &str) -> Result<Option<String>> {
+        let versions = client
+            .get_project_versions(project)
+            .await
+            .wrap_err(format!("Failed to read the versions of {project}"))?;
+        if versions.iter().any(|v| v.name == self.version) {
+            return Ok(None);
+        }
+        if !self.dry_run {
+            client
+                .post_version(project, &self.version)
+                .await
+                .wrap_err(format!(
+                    "Failed to create version {} in {project}",
+                    self.version
+                ))?;
+        }
+        Ok(Some(format!(
+            "{} version {} in {project}",
+            match self.dry_run {
+                true => "Would create",
+                false => "Created",
+            },
+            self.version
+        )))
+    }
+
+    /// Add the fix version and run the transition, stopping at the first failure
+    async fn release_issue(
+        &self,
+        client: &JiraClient,
+        issue: &Issue,
+        report: &mut IssueReport,
+    ) -> Result<()> {
+        let key = &issue.key;
+        if has_fix_version(issue, &self.version) {
+            report.done.push(format!("already in {}", self.version));
+        } else if self.dry_run {
+            report
+                .done
+                .push(format!("would add fix version {}", self.version));
+        } else {
+            client
+                .put_fix_version(key, &self.version)
+                .await
+                .wrap_err("Failed to add fix version")?;
+            report
+                .done
+                .push(format!("fix version {} added", self.version));
+        }
+
+        let Some(transition) = &self.transition else {
+            return Ok(());
+        };
+        let status = issue
+            .fields
+            .status
+            .as_ref()
+            .map(|s| s.name.as_str())
+            .unwrap_or_default();
+        if status.eq_ignore_ascii_case(transition) {
+            report.done.push(format!("already {status}"));
+        } else if self.dry_run {
+            let transitions = client.get_transitions(key, None).await?.transitions;
+            if !transitions
+                .iter()
+                .any(|t| t.name.eq_ignore_ascii_case(transition))
+            {
+                return Err(eyre!("Transition '{transition}' is not available"));
+            }
+            report.done.push(format!("would move to {transition}"));
+        } else {
+            Transition::move_to(client, key, transition)
+                .await?
+                .error_for_status()
+                .wrap_err(format!("Failed to move to {transition}"))?;
+            report.done.push(format!("moved to {transition}"));
+        }
+        Ok(())
+    }
+}
+
+impl ExecCommand for Release {
+    async fn exec(self, cfg: &Config) -> Result<String> {
+        let repo = Repository::open().wrap_err("Failed to open repository")?;
+        let (from, to) = Repository::split_range(&self.range)?;
+        let commits = repo.get_commits(to, &[repo.rev_parse(from)?])?;
+        let issue_keys = Self::issue_keys(&commits, cfg);
+        if issue_keys.is_empty() {
+            return Ok(format!("No issues referenced in {}", self.range));
+        }
+
+        let client = JiraClient::new(cfg)?;
+        let issues = client
+            .query_issue_keys(&issue_keys, &["status", "fixVersions"])
+            .await
+            .wrap_err("Failed to query the issues of the range")?;
+
+        // A project failing to get the version fails its issues only
+        let mut projects: BTreeMap<String, Option<String>> = BTreeMap::new();
+        for issue in &issues {
+            let project = project(&issue.key);
+            if projects.contains_key(&project) {
+                continue;
+            }
+            let error = match self.ensure_version(&client, &project).await {
+                Ok(Some(done)) => {
+                    println!("{done}");
+                    None
+                }
+                Ok(None) => None,
+                Err(e) => Some(format!("{e:#}")),
+            };
+            projects.insert(project, error);
+        }
+
+        let mut reports = vec![];
+        for key in issue_keys {
+            let mut report = IssueReport {
+                key: key.clone(),
+                done: vec![],
+                error: None,
+            };
+            let project_error = projects.get(&project(&key)).cloned().flatten();
+            report.error = match issues.iter().find(|issue| issue.key == key) {
+                None => Some(String::from("not found in Jira")),
+                Some(_) if project_error.is_some() => project_error,
+                Some(issue) => self
+                    .release_issue(&client, issue, &mut report)
+                    .await
+                    .err()
+                    .map(|e| format!("{e:#}")),
+            };
+            println!("{}", report.line());
+            reports.push(report);
+        }
+
+        let failed = reports.iter().filter(|r| r.error.is_some()).count();
+        match (failed, self.dry_run) {
+            (0, true) => Ok(format!(
+                "Dry run for {} issue(s), nothing was changed",
+                reports.len()
+            )),
+            (0, false) => Ok(format!(
+                "Released {} issue(s) in {}",
+                reports.len(),
+                self.version
+            )),
+            (failed, _) => Err(eyre!(
+                "{failed} of {} issue(s) failed to release",
+                reports.len()
+            ))
+            .with_suggestion(|| "Rerun the command after fixing them, completed steps are skipped"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn project_of_issue_key() {
+        let key = |k: &str| IssueKey::try_from(String::from(k)).expect("Valid issue key");
+        assert_eq!("JB", project(&key("JB-12")));
+        assert_eq!("MY_PROJ2", project(&key("MY_PROJ2-1")));
+    }
+}
@@ -1,14 +1,15 @@
-use crate::{config::Config, interactivity::issue_from_branch_or_prompt, repo::Repository};
+use crate::{
+    client::JiraClient, config::Config, interactivity::issue_from_branch_or_prompt,
+    repo::Repository,
+};
 use clap::Args;
 use color_eyre::{
     Section,
     eyre::{Result, WrapErr, eyre},
 };
 use inquire::Select;
-use jira::{
-    JiraAPIClient,
-    models::{IssueKey, PostTransitionBody, PostTransitionIdBody},
-};
+use jira::models::{IssueKey, PostTransitionBody, PostTransitionIdBody};
+use reqwest::Response;
 
 use super::shared::ExecCommand;
 
@@ -21,7 +22,7 @@ pub struct Transition {
 
 impl ExecCommand for Transition {
     async fn exec(self, cfg: &Config) -> Result<String> {
-        let client = JiraAPIClient::new(&cfg.jira_cfg)?;
+        let client = JiraClient::new(cfg)?;
 
         let maybe_repo = Repository::open().wrap_err("Failed to open repository");
         let head = match maybe_repo {
@@ -50,9 +51,48 @@ impl ExecCommand for Transition {
                     .wrap_err("No transition selected")?
             };
 
+        Transition::post(&client, &issue_key, selected_transition).await?;
+        Ok(String::default())
+    }
+}
+
+impl Transition {
+    /// Run the transition named `name`, case insensitive.
+    pub async fn move_to(
+        client: &JiraClient,
+        issue_key: &IssueKey,
+        name: &str,
+    ) -> Result<Response> {
+        let transitions = client.get_transitions(issue_key, None).await?.transitions;
+        let transition = transitions
+            .iter()
+            .find(|t| t.name.eq_ignore_ascii_case(name))
+            .cloned()
+            .ok_or(eyre!(
+                "Transition '{name}' is not available for {issue_key}"
+            ))
+            .with_note(|| {
+                format!(
+                    "Available transitions: {}",
+                    transitions
+                        .iter()
+                        .map(|t| t.name.as_str())
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                )
+            })?;
+
+        Transition::post(client, issue_key, transition).await
+    }
+
+    async fn post(
+        client: &JiraClient,
+        issue_key: &IssueKey,
+        transition: jira::models::Transition,
+    ) -> Result<Response> {
         // TODO implement terminal UI for handling this
         // Abort if there's required fields
-        if selected_transition
+        if transition
             .fields
             .into_iter()
             .any(|(_, t)| t.required && !t.has_default_value.is_some_and(|v| v))
@@ -65,14 +105,11 @@ impl ExecCommand for Transition {
         }
 
         let transition = PostTransitionBody {
-            transition: PostTransitionIdBody {
-                id: selected_transition.id,
-            },
+            transition: PostTransitionIdBody { id: transition.id },
             fields: None,
             update: None,
         };
 
-        client.post_transition(&issue_key, &transition).await?;
-        Ok(String::default())
+        Ok(client.post_transition(issue_key, &transition).await?)
     }
 }
@@ -1,12 +1,16 @@
+use crate::{client, config::Config};
 use clap::Args;
 use color_eyre::Section;
-use color_eyre::eyre::{Result, WrapErr};
+use color_eyre::eyre::{Result, WrapErr, eyre};
 use inquire::Select;
-use self_update::{
-    backends::github::{ReleaseList, Update},
-    update::Release,
+use reqwest::{
+    Client, ClientBuilder,
+    header::{ACCEPT, AUTHORIZATION, HeaderMap, HeaderValue},
 };
-use std::{env, fmt::Display, thread};
+use serde::Deserialize;
+use std::{env, fmt::Display, fs};
+
+const RELEASES_URL: &str = "https://api.github.com/repos/baarsgaard/jig/releases";
 
 #[derive(Args, Debug)]
 pub struct Upgrade {
@@ -20,72 +24,138 @@ pub struct Upgrade {
     select: bool,
 }
 
+#[derive(Deserialize, Debug, Clone)]
+struct Release {
+    tag_name: String,
+    name: Option<String>,
+    assets: Vec<ReleaseAsset>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+struct ReleaseAsset {
+    name: String,
+    browser_download_url: String,
+}
+
+impl Release {
+    fn version(&self) -> &str {
+        self.tag_name.trim_start_matches('v')
+    }
+
+    /// Binary built for the platform jig is running on
+    fn asset(&self) -> Result<&ReleaseAsset> {
+        let target = self_update::get_target();
+        self.assets
+            .iter()
+            .find(|asset| asset.name.contains(target))
+            .ok_or(eyre!(
+                "Release {} has no binary for {target}",
+                self.tag_name
+            ))
+    }
+}
+
+impl Display for Release {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name.as_deref().unwrap_or(&self.tag_name))
+    }
+}
+
 impl Upgrade {
-    pub async fn upgrade(self) -> Result<String> {
-        thread::spawn(move || {
-            let token = env::var("GITHUB_TOKEN").unwrap_or_default();
-            let target_ver = if self.select {
-                let mut builder = ReleaseList::configure();
-                let mut releases_cfg = builder.repo_owner("baarsgaard").repo_name("jig");
-                if !token.is_empty() {
-                    releases_cfg = releases_cfg.auth_token(&token);
-                }
-
-                let raw_releases = releases_cfg
-                    .build()?
-                    .fetch()
-                    .wrap_err("Unable to fetch list of releases")
-                    .with_suggestion(
-                        || "If ratelimited: export GITHUB_TOKEN='insert_token_here'",
-                    )?;
-
-                let releases = raw_releases
-                    .iter()
-                    .map(|r| JigRelease(r.to_owned()))
-                    .collect();
-
-                Select::new("Release: ", releases).prompt()?.0.version
-            } else {
-                String::default()
-            };
-
-            let current_ver = if cfg!(debug_assertions) {
-                "0.0.0"
-            } else {
-                self_update::cargo_crate_version!()
-            };
-
-            let mut builder = Update::configure();
-            let mut cfg = builder
-                .repo_owner("baarsgaard")
-                .repo_name("jig")
-                .bin_name("jig")
-                .show_output(!self.quiet)
-                .current_version(current_ver)
-                .show_download_progress(!self.quiet)
-                .no_confirm(true);
-            if !target_ver.is_empty() {
-                cfg = cfg.target_version_tag(&format!("v{target_ver}"));
-            }
-            if !token.is_empty() {
-                cfg = cfg.auth_token(&token);
+    pub async fn upgrade(self, cfg: Option<Config>) -> Result<String> {
+        let client = Self::client(cfg.as_ref())?;
+
+        let release = match self.select {
+            true => {
+                let releases = Self::get::<Vec<Release>>(&client, RELEASES_URL).await?;
+                Select::new("Release: ", releases).prompt()?
             }
+            false => Self::get::<Release>(&client, &format!("{RELEASES_URL}/latest")).await?,
+        };
+
+        let current_ver = if cfg!(debug_assertions) {
+            "0.0.0"
+        } else {
+            self_update::cargo_crate_version!()
+        };
+        // Selected releases are installed even when older
+        if !self.select && !self_update::version::bump_is_greater(current_ver, release.version())? {
+            return Ok(match self.quiet {
+                true => String::default(),
+                false => format!("Already up to date: {current_ver}"),
+            });
+        }
+
+        let asset = release.asset()?;
+        if !self.quiet {
+            println!("Downloading {}", asset.name);
+        }
+        let binary = client
+            .get(&asset.browser_download_url)
+            .send()
+            .await
+            .and_then(|res| res.error_for_status())
+            .wrap_err(format!("Unable to download {}", asset.name))?
+            .bytes()
+            .await
+            .wrap_err(format!("Unable to download {}", asset.name))?;
 
-            let _ = cfg
-                .build()?
-                .update()
-                .with_suggestion(|| "If ratelimited: export GITHUB_TOKEN='insert_token_here'")?;
+        let tmp_dir =
+            self_update::TempDir::new().wrap_err("Unable to create temporary directory")?;
+        let new_exe = tmp_dir.path().join(&asset.name);
+        fs::write(&new_exe, binary).wrap_err("Unable to write downloaded binary")?;
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(&new_exe, fs::Permissions::from_mode(0o755))
+                .wrap_err("Unable to make downloaded binary executable")?;
+        }
+        self_update::self_replace::self_replace(&new_exe)
+            .wrap_err("Unable to replace the jig executable")?;
 
-            Ok(String::default())
+        Ok(match self.quiet {
+            true => String::default(),
+            false => format!("Upgraded from {current_ver} to {}", release.version()),
         })
-        .join()
-        .expect("Self update wrapper thread panicked")
     }
-}
 
-struct JigRelease(Release);
-impl Display for JigRelease {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}", self.0.name)
+    /// Client for the GitHub API, using the proxy configured for Jira
+    fn client(cfg: Option<&Config>) -> Result<Client> {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            ACCEPT,
+            HeaderValue::from_static("application/vnd.github+json"),
+        );
+        if let Ok(token) = env::var("GITHUB_TOKEN")
+            && let Ok(mut value) = HeaderValue::from_str(&format!("Bearer {token}"))
+        {
+            value.set_sensitive(true);
+            headers.insert(AUTHORIZATION, value);
+        }
+
+        let mut builder = ClientBuilder::new()
+            .user_agent(concat!("jig/", env!("CARGO_PKG_VERSION")))
+            .default_headers(headers);
+        if let Some(proxy) = cfg
+            .map(|cfg| client::build_proxy(&cfg.proxy_cfg))
+            .transpose()?
+            .flatten()
+        {
+            builder = builder.proxy(proxy);
+        }
+        builder.build().wrap_err("Unable to build HTTP client")
+    }
+
+    async fn get<T: serde::de::DeserializeOwned>(client: &Client, url: &str) -> Result<T> {
+        client
+            .get(url)
+            .send()
+            .await
+            .and_then(|res| res.error_for_status())
+            .wrap_err("Unable to fetch releases")
+            .with_suggestion(|| "If ratelimited: export GITHUB_TOKEN='insert_token_here'")?
+            .json::<T>()
+            .await
+            .wrap_err("Unable to parse releases")
     }
 }
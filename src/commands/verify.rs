@@ -0,0 +1,315 @@
+use crate::{
+    client::JiraClient,
+    config::Config,
+    hooks::{CommitMsg, verify_status},
+    repo::{CommitInfo, Repository},
+};
+use clap::{Args, ValueHint};
+use color_eyre::eyre::{Result, WrapErr, eyre};
+use jira::models::IssueKey;
+use serde::Serialize;
+use std::{collections::BTreeMap, fs, path::PathBuf};
+
+use super::shared::ExecCommand;
+
+#[derive(Args, Debug)]
+pub struct Verify {
+    /// Commits to verify, e.g. origin/main..HEAD
+    #[arg(value_name = "RANGE", value_hint = ValueHint::Unknown)]
+    range: String,
+
+    /// Branch the commits must reference the issue of, defaults to the checked out branch
+    #[arg(short, long, value_hint = ValueHint::Unknown)]
+    branch: Option<String>,
+
+    /// Look up each referenced issue, failing on missing issues or disallowed statuses
+    #[arg(short = 'i', long)]
+    check_issues: bool,
+
+    /// Write a JUnit XML report
+    #[arg(long, value_name = "FILE", value_hint = ValueHint::FilePath)]
+    junit: Option<PathBuf>,
+
+    /// Write a JSON report
+    #[arg(long, value_name = "FILE", value_hint = ValueHint::FilePath)]
+    json: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum Outcome {
+    Passed,
+    Failed,
+    /// Exempt commits, e.g. merges
+    Skipped,
+}
+
+#[derive(Debug, Serialize)]
+struct CommitReport {
+    id: String,
+    subject: String,
+    issue_key: Option<String>,
+    outcome: Outcome,
+    failures: Vec<String>,
+    warnings: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct VerifyReport {
+    range: String,
+    passed: usize,
+    failed: usize,
+    skipped: usize,
+    commits: Vec<CommitReport>,
+}
+
+fn xml_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+impl VerifyReport {
+    fn new(range: &str, commits: Vec<CommitReport>) -> Self {
+        let count = |outcome| commits.iter().filter(|c| c.outcome == outcome).count();
+        VerifyReport {
+            range: range.to_string(),
+            passed: count(Outcome::Passed),
+            failed: count(Outcome::Failed),
+            skipped: count(Outcome::Skipped),
+            commits,
+        }
+    }
+
+    fn summary(&self) -> String {
+        let mut lines = vec![];
+        for commit in &self.commits {
+            let label = match commit.outcome {
+                Outcome::Passed => "ok  ",
+                Outcome::Failed => "FAIL",
+                Outcome::Skipped => "skip",
+            };
+            lines.push(format!("{label} {:.8} {}", commit.id, commit.subject));
+            lines.extend(commit.failures.iter().map(|f| format!("       {f}")));
+            lines.extend(commit.warnings.iter().map(|w| format!("       {w}")));
+        }
+        lines.push(format!(
+            "{} commit(s) in {}: {} passed, {} failed, {} skipped",
+            self.commits.len(),
+            self.range,
+            self.passed,
+            self.failed,
+            self.skipped
+        ));
+        lines.join("\n")
+    }
+
+    /// One test case per commit, failures share a single failure element
+    fn junit(&self) -> String {
+        let mut xml = vec![
+            String::from(r#"<?xml version="1.0" encoding="UTF-8"?>"#),
+            format!(
+                r#"<testsuites name="jig verify" tests="{}" failures="{}" skipped="{}">"#,
+                self.commits.len(),
+                self.failed,
+                self.skipped
+            ),
+            format!(
+                r#"  <testsuite name="{}" tests="{}" failures="{}" skipped="{}">"#,
+                xml_escape(&self.range),
+                self.commits.len(),
+                self.failed,
+                self.skipped
+            ),
+        ];
+        for commit in &self.commits {
+            let testcase = format!(
+                r#"    <testcase classname="jig.verify" name="{:.8} {}""#,
+                commit.id,
+                xml_escape(&commit.subject)
+            );
+            match commit.outcome {
+                Outcome::Passed => xml.push(format!("{testcase}/>")),
+                Outcome::Skipped => {
+                    xml.push(format!("{testcase}>"));
+                    xml.push(String::from(r#"      <skipped message="exempt commit"/>"#));
+                    xml.push(String::from("    </testcase>"));
+                }
+                Outcome::Failed => {
+                    xml.push(format!("{testcase}>"));
+                    xml.push(format!(
+                        r#"      <failure message="{}">{}</failure>"#,
+                        xml_escape(&commit.failures.join(", ")),
+                        xml_escape(&commit.failures.join("\n"))
+                    ));
+                    xml.push(String::from("    </testcase>"));
+                }
+            }
+        }
+        xml.push(String::from("  </testsuite>"));
+        xml.push(String::from("</testsuites>\n"));
+        xml.join("\n")
+    }
+}
+
+impl Verify {
+    /// Issue key the commits must match, None when the branch does not contain one
+    fn branch_key(&self, repo: &Repository) -> Result<Option<IssueKey>> {
+        let branch = match &self.branch {
+            Some(branch) => branch.to_owned(),
+            // CI usually checks out a detached HEAD
+            None => {
+                let branch = repo.get_branch_name()?;
+                match repo.rev_parse(&format!("refs/heads/{branch}")) {
+                    Ok(_) => branch,
+                    Err(_) => return Ok(None),
+                }
+            }
+        };
+        Ok(IssueKey::try_from(branch).ok())
+    }
+
+    fn verify_commit(
+        commit: &CommitInfo,
+        branch_key: Option<&IssueKey>,
+        cfg: &Config,
+    ) -> Result<CommitReport> {
+        let verification = CommitMsg::verify(
+            &commit.message,
+            commit.parent_count,
+            branch_key,
+            &cfg.hooks_cfg,
+        )?;
+        let mut report = CommitReport {
+            id: commit.id.clone(),
+            subject: commit.subject().to_string(),
+            issue_key: None,
+            outcome: Outcome::Skipped,
+            failures: vec![],
+            warnings: vec![],
+        };
+        if let Some(verification) = verification {
+            report.issue_key = verification.issue_key.map(|key| key.to_string());
+            report.failures = verification.failures;
+            report.warnings = verification.warnings;
+            report.outcome = Outcome::Passed;
+        }
+        Ok(report)
+    }
+
+    /// Failure per referenced issue that is missing or not in an allowed status
+    async fn check_issues(
+        cfg: &Config,
+        commits: &[CommitReport],
+    ) -> Result<BTreeMap<String, String>> {
+        let client = JiraClient::new(cfg)?;
+        let mut failures = BTreeMap::new();
+        for issue_key in commits.iter().filter_map(|c| c.issue_key.as_ref()) {
+            if failures.contains_key(issue_key) {
+                continue;
+            }
+            let key = IssueKey::try_from(issue_key.clone())?;
+            let failure =
+                match verify_status(&client, &key, &cfg.hooks_cfg.allowed_issue_statuses).await {
+                    Ok(()) => String::new(),
+                    Err(e) => e.to_string(),
+                };
+            failures.insert(issue_key.clone(), failure);
+        }
+        failures.retain(|_, failure| !failure.is_empty());
+        Ok(failures)
+    }
+
+    fn write(path: &PathBuf, content: &str) -> Result<()> {
+        fs::write(path, content).wrap_err(format!("Failed to write report: {}", path.display()))
+    }
+}
+
+impl ExecCommand for Verify {
+    async fn exec(self, cfg: &Config) -> Result<String> {
+        let repo = Repository::open().wrap_err("Failed to open repository")?;
+        let (base, tip) = Repository::split_range(&self.range)?;
+        // Fail on typos instead of verifying the entire history
+        let base = repo.rev_parse(base)?;
+        let branch_key = self.branch_key(&repo)?;
+
+        let mut commits = repo
+            .get_commits(tip, &[base])?
+            .iter()
+            .map(|commit| Self::verify_commit(commit, branch_key.as_ref(), cfg))
+            .collect::<Result<Vec<_>>>()?;
+
+        if self.check_issues {
+            let issue_failures = Self::check_issues(cfg, &commits).await?;
+            for commit in &mut commits {
+                if let Some(failure) = commit
+                    .issue_key
+                    .as_ref()
+                    .and_then(|key| issue_failures.get(key))
+                {
+                    commit.failures.push(failure.to_owned());
+                }
+            }
+        }
+        for commit in &mut commits {
+            if !commit.failures.is_empty() {
+                commit.outcome = Outcome::Failed;
+            }
+        }
+
+        let report = VerifyReport::new(&self.range, commits);
+        if let Some(path) = &self.junit {
+            Self::write(path, &report.junit())?;
+        }
+        if let Some(path) = &self.json {
+            let json =
+                serde_json::to_string_pretty(&report).wrap_err("Failed to serialize report")?;
+            Self::write(path, &json)?;
+        }
+
+        println!("{}", report.summary());
+        match report.failed {
+            0 => Ok(String::default()),
+            failed => Err(eyre!("{failed} commit(s) failed verification")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn commit(subject: &str, outcome: Outcome, failures: &[&str]) -> CommitReport {
+        CommitReport {
+            id: String::from("0123456789abcdef"),
+            subject: String::from(subject),
+            issue_key: None,
+            outcome,
+            failures: failures.iter().map(|f| f.to_string()).collect(),
+            warnings: vec![],
+        }
+    }
+
+    #[test]
+    fn junit_report() {
+        let report = VerifyReport::new(
+            "main..HEAD",
+            vec![
+                commit("JB-1 Add <the> thing", Outcome::Passed, &[]),
+                commit("Add the thing", Outcome::Failed, &["missing issue key"]),
+                commit("Merge branch 'main'", Outcome::Skipped, &[]),
+            ],
+        );
+        let junit = report.junit();
+        assert!(
+            junit.contains(r#"<testsuites name="jig verify" tests="3" failures="1" skipped="1">"#)
+        );
+        assert!(junit.contains(r#"name="01234567 JB-1 Add &lt;the&gt; thing"/>"#));
+        assert!(
+            junit.contains(r#"<failure message="missing issue key">missing issue key</failure>"#)
+        );
+        assert!(junit.contains(r#"name="01234567 Merge branch &apos;main&apos;">"#));
+    }
+}
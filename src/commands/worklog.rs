@@ -1,6 +1,7 @@
 use std::fmt::Debug;
 
 use crate::{
+    client::JiraClient,
     config::Config,
     interactivity::{issue_from_branch_or_prompt, now},
     repo::Repository,
@@ -9,10 +10,7 @@ use chrono::{NaiveDate, Weekday};
 use clap::{Args, ValueHint};
 use color_eyre::eyre::{Result, WrapErr, eyre};
 use inquire::DateSelect;
-use jira::{
-    JiraAPIClient,
-    models::{IssueKey, PostWorklogBody, WorklogDuration},
-};
+use jira::models::{IssueKey, PostWorklogBody, WorklogDuration};
 
 use super::shared::ExecCommand;
 
@@ -46,7 +44,7 @@ pub struct Worklog {
 
 impl ExecCommand for Worklog {
     async fn exec(self, cfg: &Config) -> Result<String> {
-        let client = JiraAPIClient::new(&cfg.jira_cfg)?;
+        let client = JiraClient::new(cfg)?;
         let maybe_repo = Repository::open().wrap_err("Failed to open repository");
         let head = match maybe_repo {
             Ok(repo) => repo.get_branch_name()?,
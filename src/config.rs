@@ -1,17 +1,24 @@
 use color_eyre::{
     Section,
-    eyre::{Result, WrapErr},
+    eyre::{Result, WrapErr, eyre},
 };
 use etcetera::base_strategy::{BaseStrategy, choose_base_strategy};
 use jira::{Credential, JiraClientConfig};
+use reqwest::{Certificate, Identity};
 use serde::{Deserialize, Serialize};
-use std::{fs, path::PathBuf, sync::OnceLock};
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+    sync::OnceLock,
+};
 use toml::from_str;
+use url::Url;
 
 // Proof of concept
 static CONFIG_FILE: OnceLock<PathBuf> = OnceLock::new();
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
 #[serde(deny_unknown_fields)]
 pub struct RawConfig {
     /// server url or <domain>.atlassian.net
@@ -24,8 +31,23 @@ pub struct RawConfig {
     pub jira_timeout_seconds: Option<u64>,
     /// Accept invalid TLS certificates.
     pub insecure_skip_tls_verify: Option<bool>,
-    /// Path to PEM encoded CA certificate
+    /// Path to PEM encoded CA certificate(s).
+    /// Accepts a single file, a bundle or a directory of PEM files.
     pub tls_ca_certificate_path: Option<String>,
+    /// Path to PEM encoded client certificate used for mutual TLS.
+    pub tls_client_certificate_path: Option<String>,
+    /// Path to PEM encoded private key belonging to the client certificate.
+    pub tls_client_key_path: Option<String>,
+    /// Proxy used for all HTTP(S) requests.
+    /// Defaults to HTTPS_PROXY, HTTP_PROXY or ALL_PROXY environment variables.
+    pub proxy_url: Option<String>,
+    /// Comma separated list of hosts that bypass the proxy.
+    /// Defaults to NO_PROXY environment variable.
+    pub no_proxy: Option<String>,
+    /// Username for proxy basic authentication.
+    pub proxy_username: Option<String>,
+    /// Password for proxy basic authentication.
+    pub proxy_password: Option<String>,
     /// Max number of issues to fetch.
     /// max 1500.
     pub max_query_results: Option<u32>,
@@ -37,16 +59,155 @@ pub struct RawConfig {
     pub git_hooks: Option<GitHooksRawConfig>,
 }
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
 pub struct GitHooksRawConfig {
     pub allow_branch_missing_issue_key: Option<bool>,
     pub allow_branch_and_commit_msg_mismatch: Option<bool>,
+    pub prepare_commit_msg_issue_context: Option<bool>,
+    pub reference_style: Option<ReferenceStyle>,
+    /// Prefix commit messages with a Conventional Commit type derived from the issue type
+    pub conventional_commits: Option<bool>,
+    /// Issue type name to Conventional Commit type
+    pub conventional_commit_types: Option<BTreeMap<String, String>>,
+    pub conventional_commit_allowed_types: Option<Vec<String>>,
+    /// Commit message policy rules
+    pub policy: Option<PolicyRawConfig>,
+    /// Verify the issue exists and is not done when committing
+    pub validate_issue_status: Option<bool>,
+    /// Statuses accepted by validate_issue_status, defaults to any status not in the Done category
+    pub allowed_issue_statuses: Option<Vec<String>>,
+    /// Transition run by the post-checkout hook when checking out a branch of an issue in the To Do category
+    pub post_checkout_transition: Option<String>,
+    /// Assign the issue to yourself in the post-checkout hook
+    pub post_checkout_assign: Option<bool>,
+    /// Integration branches on which the post-merge hook runs
+    pub post_merge_branches: Option<Vec<String>>,
+    /// Transition offered by the post-merge hook for the merged issues, disabled when unset
+    pub post_merge_transition: Option<String>,
+    /// Commits skipped by the commit-msg and pre-push hooks, defaults to all
+    pub exempt_commits: Option<Vec<ExemptCommit>>,
+    /// Branch naming policy
+    pub branch_policy: Option<BranchPolicyRawConfig>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct BranchPolicyRawConfig {
+    /// Reject new branches violating the policy in the reference-transaction and pre-push hooks
+    pub enabled: Option<bool>,
+    pub require_issue_key: Option<bool>,
+    /// Text allowed before the issue key, e.g. "feature/". Any prefix when empty
+    pub allowed_prefixes: Option<Vec<String>>,
+    pub max_length: Option<usize>,
+    /// Branch names exempt from the policy, `*` matches any characters
+    pub allowlist: Option<Vec<String>>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct PolicyRawConfig {
+    pub subject_max_length: Option<RuleLevel>,
+    pub subject_max_length_limit: Option<usize>,
+    /// First word of the subject is in imperative mood, e.g. 'Add' not 'Added'
+    pub imperative_mood: Option<RuleLevel>,
+    pub subject_trailing_period: Option<RuleLevel>,
+    pub body_blank_line: Option<RuleLevel>,
+    pub body_wrap_width: Option<RuleLevel>,
+    pub body_wrap_width_limit: Option<usize>,
+    pub deny_regex: Option<RuleLevel>,
+    pub deny_regex_patterns: Option<Vec<String>>,
 }
 
 #[derive(Debug, Clone)]
 pub struct GitHooksConfig {
     pub allow_branch_missing_issue_key: bool,
     pub allow_branch_and_commit_msg_mismatch: bool,
+    pub prepare_commit_msg_issue_context: bool,
+    pub reference_style: ReferenceStyle,
+    pub conventional_commits: bool,
+    pub conventional_commit_types: BTreeMap<String, String>,
+    pub conventional_commit_allowed_types: Vec<String>,
+    pub policy: PolicyConfig,
+    pub validate_issue_status: bool,
+    pub allowed_issue_statuses: Vec<String>,
+    pub post_checkout_transition: Option<String>,
+    pub post_checkout_assign: bool,
+    pub post_merge_branches: Vec<String>,
+    pub post_merge_transition: Option<String>,
+    pub exempt_commits: Vec<ExemptCommit>,
+    pub branch_policy: BranchPolicyConfig,
+}
+
+#[derive(Debug, Clone)]
+pub struct BranchPolicyConfig {
+    pub enabled: bool,
+    pub require_issue_key: bool,
+    pub allowed_prefixes: Vec<String>,
+    pub max_length: usize,
+    pub allowlist: Vec<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct PolicyConfig {
+    pub subject_max_length: RuleLevel,
+    pub subject_max_length_limit: usize,
+    pub imperative_mood: RuleLevel,
+    pub subject_trailing_period: RuleLevel,
+    pub body_blank_line: RuleLevel,
+    pub body_wrap_width: RuleLevel,
+    pub body_wrap_width_limit: usize,
+    pub deny_regex: RuleLevel,
+    pub deny_regex_patterns: Vec<String>,
+}
+
+/// Severity of a commit message policy rule.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RuleLevel {
+    /// Reject the commit
+    Error,
+    /// Print the violation and continue
+    Warn,
+    #[default]
+    Off,
+}
+
+/// Where the commit-msg hook places the issue key.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ReferenceStyle {
+    /// `JB-12 Subject`
+    #[default]
+    Prefix,
+    /// `Subject (JB-12)`
+    Suffix,
+    /// `[JB-12] Subject`
+    Bracket,
+    /// `Refs: JB-12` git trailer
+    Trailer,
+}
+
+/// Commits with messages generated by git, exempt from the issue key checks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ExemptCommit {
+    /// `Merge branch 'x' into y`
+    Merge,
+    /// `Revert "Subject"`
+    Revert,
+    /// Commits made by `git cherry-pick`, including `-x` messages
+    CherryPick,
+    /// `fixup!`, `squash!` and `amend!` commits
+    Fixup,
+    /// `git notes` edits
+    Notes,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ProxyConfig {
+    /// Explicitly configured proxy, None falls back to environment variables.
+    pub url: Option<Url>,
+    pub no_proxy: Option<String>,
+    pub username: Option<String>,
+    pub password: Option<String>,
 }
 
 #[derive(Debug, Clone)]
@@ -55,6 +216,9 @@ pub struct Config {
     pub enable_comment_prompts: Option<bool>,
     pub one_transition_auto_move: Option<bool>,
     pub jira_cfg: JiraClientConfig,
+    /// PEM encoded client certificate and private key
+    pub tls_client_identity: Option<String>,
+    pub proxy_cfg: ProxyConfig,
     pub hooks_cfg: GitHooksConfig,
 }
 
@@ -91,45 +255,252 @@ impl Config {
         }
         cfg.jira_url = url;
 
-        Ok(Config::from(cfg))
+        Config::try_from(cfg)
     }
 }
 
 impl From<Option<GitHooksRawConfig>> for GitHooksConfig {
     fn from(value: Option<GitHooksRawConfig>) -> Self {
-        if let Some(cfg) = value {
-            GitHooksConfig {
-                allow_branch_missing_issue_key: cfg.allow_branch_missing_issue_key.unwrap_or(false),
-                allow_branch_and_commit_msg_mismatch: cfg
-                    .allow_branch_and_commit_msg_mismatch
-                    .unwrap_or(false),
-            }
-        } else {
-            GitHooksConfig {
-                allow_branch_missing_issue_key: false,
-                allow_branch_and_commit_msg_mismatch: false,
+        let cfg = value.unwrap_or_default();
+        GitHooksConfig {
+            allow_branch_missing_issue_key: cfg.allow_branch_missing_issue_key.unwrap_or(false),
+            allow_branch_and_commit_msg_mismatch: cfg
+                .allow_branch_and_commit_msg_mismatch
+                .unwrap_or(false),
+            prepare_commit_msg_issue_context: cfg.prepare_commit_msg_issue_context.unwrap_or(false),
+            reference_style: cfg.reference_style.unwrap_or_default(),
+            conventional_commits: cfg.conventional_commits.unwrap_or(false),
+            conventional_commit_types: cfg.conventional_commit_types.unwrap_or_else(|| {
+                [
+                    ("Bug", "fix"),
+                    ("Story", "feat"),
+                    ("Task", "chore"),
+                    ("Chore", "chore"),
+                ]
+                .into_iter()
+                .map(|(issue_type, commit_type)| (issue_type.into(), commit_type.into()))
+                .collect()
+            }),
+            conventional_commit_allowed_types: cfg
+                .conventional_commit_allowed_types
+                .unwrap_or_else(|| {
+                    [
+                        "build", "chore", "ci", "docs", "feat", "fix", "perf", "refactor",
+                        "revert", "style", "test",
+                    ]
+                    .map(String::from)
+                    .to_vec()
+                }),
+            policy: PolicyConfig::from(cfg.policy),
+            validate_issue_status: cfg.validate_issue_status.unwrap_or(false),
+            allowed_issue_statuses: cfg.allowed_issue_statuses.unwrap_or_default(),
+            post_checkout_transition: cfg
+                .post_checkout_transition
+                .filter(|t| !t.trim().is_empty()),
+            post_checkout_assign: cfg.post_checkout_assign.unwrap_or(false),
+            post_merge_branches: cfg
+                .post_merge_branches
+                .unwrap_or_else(|| ["main", "master", "develop"].map(String::from).to_vec()),
+            post_merge_transition: cfg.post_merge_transition.filter(|t| !t.trim().is_empty()),
+            exempt_commits: cfg.exempt_commits.unwrap_or_else(|| {
+                vec![
+                    ExemptCommit::Merge,
+                    ExemptCommit::Revert,
+                    ExemptCommit::CherryPick,
+                    ExemptCommit::Fixup,
+                    ExemptCommit::Notes,
+                ]
+            }),
+            branch_policy: BranchPolicyConfig::from(cfg.branch_policy),
+        }
+    }
+}
+
+impl From<Option<PolicyRawConfig>> for PolicyConfig {
+    fn from(value: Option<PolicyRawConfig>) -> Self {
+        let cfg = value.unwrap_or_default();
+        PolicyConfig {
+            subject_max_length: cfg.subject_max_length.unwrap_or_default(),
+            subject_max_length_limit: cfg.subject_max_length_limit.unwrap_or(72),
+            imperative_mood: cfg.imperative_mood.unwrap_or_default(),
+            subject_trailing_period: cfg.subject_trailing_period.unwrap_or_default(),
+            body_blank_line: cfg.body_blank_line.unwrap_or_default(),
+            body_wrap_width: cfg.body_wrap_width.unwrap_or_default(),
+            body_wrap_width_limit: cfg.body_wrap_width_limit.unwrap_or(72),
+            deny_regex: cfg.deny_regex.unwrap_or_default(),
+            deny_regex_patterns: cfg.deny_regex_patterns.unwrap_or_default(),
+        }
+    }
+}
+
+impl From<Option<BranchPolicyRawConfig>> for BranchPolicyConfig {
+    fn from(value: Option<BranchPolicyRawConfig>) -> Self {
+        let cfg = value.unwrap_or_default();
+        BranchPolicyConfig {
+            enabled: cfg.enabled.unwrap_or(false),
+            require_issue_key: cfg.require_issue_key.unwrap_or(true),
+            allowed_prefixes: cfg.allowed_prefixes.unwrap_or_default(),
+            // Length of the branch names created by jig branch
+            max_length: cfg.max_length.unwrap_or(51),
+            allowlist: cfg.allowlist.unwrap_or_else(|| {
+                ["main", "master", "develop", "release/*", "hotfix/*"]
+                    .map(String::from)
+                    .to_vec()
+            }),
+        }
+    }
+}
+
+impl ProxyConfig {
+    const ENV_VARS: [&str; 6] = [
+        "HTTPS_PROXY",
+        "https_proxy",
+        "HTTP_PROXY",
+        "http_proxy",
+        "ALL_PROXY",
+        "all_proxy",
+    ];
+
+    /// Any proxy setting in the config, otherwise the environment variables are left to the clients.
+    pub fn is_configured(&self) -> bool {
+        self.url.is_some() || self.username.is_some() || self.no_proxy.is_some()
+    }
+
+    /// Configured proxy or the first proxy environment variable set, with its source.
+    /// Environment variables are read through `env`, usually `|var| std::env::var(var).ok()`.
+    pub fn effective_url(&self, env: impl Fn(&str) -> Option<String>) -> Option<(String, String)> {
+        if let Some(url) = &self.url {
+            return Some((url.to_string(), String::from("config")));
+        }
+
+        Self::ENV_VARS.iter().find_map(|var| match env(var) {
+            Some(url) if !url.trim().is_empty() => Some((url, var.to_string())),
+            _ => None,
+        })
+    }
+
+    /// Configured no_proxy list or the NO_PROXY environment variable.
+    pub fn effective_no_proxy(&self, env: impl Fn(&str) -> Option<String>) -> Option<String> {
+        self.no_proxy.clone().or_else(|| {
+            ["NO_PROXY", "no_proxy"]
+                .iter()
+                .find_map(|var| env(var))
+                .filter(|v| !v.trim().is_empty())
+        })
+    }
+
+    /// Human readable summary of the proxy setup, credentials are masked.
+    pub fn describe(&self) -> String {
+        let env = |var: &str| std::env::var(var).ok();
+        let Some((url, source)) = self.effective_url(env) else {
+            return String::from("none");
+        };
+
+        let mut summary = match Url::parse(&url) {
+            Ok(mut parsed) if !parsed.password().unwrap_or_default().is_empty() => {
+                let _ = parsed.set_password(Some("***"));
+                format!("{parsed} ({source})")
             }
+            _ => format!("{url} ({source})"),
+        };
+        if let Some(username) = &self.username {
+            summary.push_str(&format!(", authenticating as '{username}'"));
+        }
+        if let Some(no_proxy) = self.effective_no_proxy(env) {
+            summary.push_str(&format!(", bypassed for: {no_proxy}"));
+        }
+
+        summary
+    }
+}
+
+impl TryFrom<&RawConfig> for ProxyConfig {
+    type Error = color_eyre::Report;
+
+    fn try_from(cfg: &RawConfig) -> Result<Self> {
+        let non_empty = |value: &Option<String>| {
+            value
+                .as_ref()
+                .map(|v| v.trim().to_string())
+                .filter(|v| !v.is_empty())
+        };
+
+        let url = match non_empty(&cfg.proxy_url) {
+            Some(url) => Some(
+                Url::parse(&url)
+                    .wrap_err("Config load error: proxy_url")
+                    .with_suggestion(|| "Expected format: http://proxy.example.com:3128")?,
+            ),
+            None => None,
+        };
+
+        let username = non_empty(&cfg.proxy_username);
+        let password = non_empty(&cfg.proxy_password);
+        if password.is_some() && username.is_none() {
+            Err(eyre!(
+                "Config load error: proxy_password without proxy_username"
+            ))?
         }
+
+        Ok(ProxyConfig {
+            url,
+            no_proxy: non_empty(&cfg.no_proxy),
+            username,
+            password,
+        })
     }
 }
 
-impl From<RawConfig> for Config {
-    fn from(cfg: RawConfig) -> Self {
+impl TryFrom<RawConfig> for Config {
+    type Error = color_eyre::Report;
+
+    fn try_from(cfg: RawConfig) -> Result<Self> {
+        let proxy_cfg = ProxyConfig::try_from(&cfg)?;
         let credential = if let Some(pat) = cfg.pat_token {
             Credential::PersonalAccessToken(pat)
         } else {
             Credential::Anonymous
         };
 
-        let ca_certificate = if let Some(path) = cfg.tls_ca_certificate_path
-            && !path.trim().is_empty()
-        {
-            fs::read_to_string(path).ok()
-        } else {
-            None
+        let ca_certificate = match non_empty_path(cfg.tls_ca_certificate_path) {
+            Some(path) => Some(
+                read_ca_certificates(&path)
+                    .wrap_err("Config load error: tls_ca_certificate_path")
+                    .with_note(|| format!("path: {}", path.to_string_lossy()))?,
+            ),
+            None => None,
+        };
+
+        let tls_client_identity = match (
+            non_empty_path(cfg.tls_client_certificate_path),
+            non_empty_path(cfg.tls_client_key_path),
+        ) {
+            (Some(cert_path), Some(key_path)) => Some(
+                read_client_identity(&cert_path, &key_path)
+                    .wrap_err("Config load error: tls_client_certificate_path/tls_client_key_path")
+                    .with_note(|| format!("certificate: {}", cert_path.to_string_lossy()))
+                    .with_note(|| format!("key: {}", key_path.to_string_lossy()))?,
+            ),
+            (None, None) => None,
+            _ => Err(eyre!("Config load error: incomplete client certificate")).with_suggestion(
+                || "Set both tls_client_certificate_path and tls_client_key_path",
+            )?,
         };
 
-        Config {
+        let hooks_cfg = GitHooksConfig::from(cfg.git_hooks);
+        if hooks_cfg.conventional_commits
+            && matches!(
+                hooks_cfg.reference_style,
+                ReferenceStyle::Prefix | ReferenceStyle::Bracket
+            )
+        {
+            Err(eyre!(
+                "Config load error: conventional_commits require the type at the start of the subject"
+            ))
+            .with_suggestion(|| "Set reference_style to 'suffix' or 'trailer'")?
+        }
+
+        Ok(Config {
             issue_query: cfg.issue_query,
             enable_comment_prompts: cfg.enable_comment_prompts,
             one_transition_auto_move: cfg.one_transition_auto_move,
@@ -141,9 +512,94 @@ impl From<RawConfig> for Config {
                 insecure_skip_tls_verify: cfg.insecure_skip_tls_verify.unwrap_or(false),
                 ca_certificate,
             },
-            hooks_cfg: GitHooksConfig::from(cfg.git_hooks),
+            tls_client_identity,
+            proxy_cfg,
+            hooks_cfg,
+        })
+    }
+}
+
+/// Expand a leading `~` and discard blank paths.
+fn non_empty_path(path: Option<String>) -> Option<PathBuf> {
+    let path = path?;
+    let path = path.trim();
+    if path.is_empty() {
+        return None;
+    }
+
+    match (path.strip_prefix("~/"), std::env::var("HOME")) {
+        (Some(rest), Ok(home)) => Some(PathBuf::from(home).join(rest)),
+        _ => Some(PathBuf::from(path)),
+    }
+}
+
+/// Read one or more PEM encoded CA certificates from a file or a directory.
+/// Directories are read non-recursively, only `.pem`, `.crt` and `.cer` files are included.
+fn read_ca_certificates(path: &Path) -> Result<String> {
+    let files = if path.is_dir() {
+        let mut files = fs::read_dir(path)
+            .wrap_err("Unable to read CA certificate directory")?
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .filter(|p| {
+                p.is_file()
+                    && p.extension()
+                        .and_then(|ext| ext.to_str())
+                        .is_some_and(|ext| matches!(ext, "pem" | "crt" | "cer"))
+            })
+            .collect::<Vec<PathBuf>>();
+        files.sort();
+
+        if files.is_empty() {
+            Err(eyre!(
+                "No .pem, .crt or .cer files found in CA certificate directory"
+            ))?
+        }
+        files
+    } else {
+        vec![path.to_path_buf()]
+    };
+
+    let mut bundle = String::new();
+    for file in files {
+        let pem = fs::read_to_string(&file).wrap_err(format!(
+            "Unable to read CA certificate: {}",
+            file.to_string_lossy()
+        ))?;
+
+        let certs = Certificate::from_pem_bundle(pem.as_bytes()).wrap_err(format!(
+            "Invalid PEM certificate: {}",
+            file.to_string_lossy()
+        ))?;
+        if certs.is_empty() {
+            Err(eyre!(
+                "No certificates found in: {}",
+                file.to_string_lossy()
+            ))?
         }
+
+        bundle.push_str(pem.trim_end());
+        bundle.push('\n');
     }
+
+    Ok(bundle)
+}
+
+/// Read and validate a PEM encoded client certificate and its private key.
+fn read_client_identity(cert_path: &Path, key_path: &Path) -> Result<String> {
+    let cert = fs::read_to_string(cert_path).wrap_err(format!(
+        "Unable to read client certificate: {}",
+        cert_path.to_string_lossy()
+    ))?;
+    let key = fs::read_to_string(key_path).wrap_err(format!(
+        "Unable to read client key: {}",
+        key_path.to_string_lossy()
+    ))?;
+
+    let identity = format!("{}\n{}\n", cert.trim_end(), key.trim_end());
+    Identity::from_pem(identity.as_bytes())
+        .wrap_err("Invalid client certificate or key, expected PEM encoding")?;
+
+    Ok(identity)
 }
 
 pub fn config_file() -> PathBuf {
@@ -163,7 +619,11 @@ fn config_dir() -> PathBuf {
     path
 }
 
-#[allow(dead_code)]
+/// Hooks installed by `jig hook install --global`, used as the global core.hooksPath
+pub fn global_hooks_dir() -> PathBuf {
+    config_dir().join("hooks")
+}
+
 pub fn cache_dir() -> PathBuf {
     let strategy = choose_base_strategy().expect("Unable to find the config directory!");
     let mut path = strategy.cache_dir();
@@ -236,3 +696,111 @@ pub fn merge_toml_values(left: toml::Value, right: toml::Value, merge_depth: usi
         (_, value) => value,
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("jig-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn unreadable_ca_certificates() {
+        let dir = temp_dir("ca-certificates");
+        let error = |path: &Path| read_ca_certificates(path).unwrap_err().to_string();
+
+        assert!(error(&dir.join("missing.pem")).starts_with("Unable to read CA certificate"));
+
+        fs::write(dir.join("notes.txt"), "Not a certificate").unwrap();
+        assert!(error(&dir).starts_with("No .pem, .crt or .cer files found"));
+
+        let truncated = dir.join("truncated.pem");
+        fs::write(&truncated, "-----BEGIN CERTIFICATE-----\nMIIB\n").unwrap();
+        assert!(read_ca_certificates(&truncated).is_err());
+        // Directories fail on any invalid file
+        assert!(read_ca_certificates(&dir).is_err());
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn incomplete_client_identity() {
+        let dir = temp_dir("client-identity");
+        let cert = dir.join("client.crt");
+        let key = dir.join("client.key");
+        fs::write(
+            &cert,
+            "-----BEGIN CERTIFICATE-----\nMIIB\n-----END CERTIFICATE-----\n",
+        )
+        .unwrap();
+
+        let error = read_client_identity(&cert, &key).unwrap_err().to_string();
+        assert!(error.starts_with("Unable to read client key"));
+
+        fs::write(&key, "").unwrap();
+        let error = read_client_identity(&cert, &key).unwrap_err().to_string();
+        assert_eq!(
+            "Invalid client certificate or key, expected PEM encoding",
+            error
+        );
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn conventional_commits_need_type_first() {
+        let raw = |reference_style| RawConfig {
+            git_hooks: Some(GitHooksRawConfig {
+                conventional_commits: Some(true),
+                reference_style,
+                ..GitHooksRawConfig::default()
+            }),
+            ..RawConfig::default()
+        };
+
+        assert!(Config::try_from(raw(None)).is_err());
+        assert!(Config::try_from(raw(Some(ReferenceStyle::Bracket))).is_err());
+        assert!(Config::try_from(raw(Some(ReferenceStyle::Trailer))).is_ok());
+    }
+
+    #[test]
+    fn effective_no_proxy() {
+        let env = |var: &str| match var {
+            "NO_PROXY" => Some(String::from("env.example.com")),
+            "HTTPS_PROXY" => Some(String::from("http://proxy.example.com:3128")),
+            _ => None,
+        };
+        let from_env = ProxyConfig::default();
+        assert!(!from_env.is_configured());
+        assert_eq!(
+            Some(String::from("env.example.com")),
+            from_env.effective_no_proxy(env)
+        );
+
+        // Applies to the proxy from the environment
+        let no_proxy_only = ProxyConfig {
+            no_proxy: Some(String::from("jira.example.com")),
+            ..ProxyConfig::default()
+        };
+        assert!(no_proxy_only.is_configured());
+        assert_eq!(
+            Some(String::from("jira.example.com")),
+            no_proxy_only.effective_no_proxy(env)
+        );
+        assert_eq!(
+            Some((
+                String::from("http://proxy.example.com:3128"),
+                String::from("HTTPS_PROXY")
+            )),
+            no_proxy_only.effective_url(env)
+        );
+
+        let blank = |var: &str| (var == "NO_PROXY").then(|| String::from(" "));
+        assert_eq!(None, from_env.effective_no_proxy(blank));
+        assert_eq!(None, from_env.effective_url(blank));
+    }
+}
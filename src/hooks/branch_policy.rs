@@ -0,0 +1,144 @@
+use super::Rejection;
+use crate::{config::BranchPolicyConfig, repo::Repository};
+use color_eyre::eyre::{Report, Result, WrapErr};
+use jira::models::IssueKey;
+use regex::Regex;
+
+/// `*` matches any characters, including `/`
+fn is_allowlisted(branch: &str, allowlist: &[String]) -> Result<bool> {
+    for pattern in allowlist {
+        let pattern_re = Regex::new(&format!(
+            "^{}$",
+            regex::escape(pattern).replace(r"\*", ".*")
+        ))
+        .wrap_err(format!("Invalid branch allowlist pattern: {pattern}"))?;
+        if pattern_re.is_match(branch) {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+/// Reasons `branch` violates the naming policy, empty if it complies or is allowlisted
+pub fn check(branch: &str, cfg: &BranchPolicyConfig) -> Result<Vec<String>> {
+    if is_allowlisted(branch, &cfg.allowlist)? {
+        return Ok(vec![]);
+    }
+
+    let mut violations = vec![];
+    let issue_key = IssueKey::try_from(branch.to_string()).ok();
+    match &issue_key {
+        None if cfg.require_issue_key => violations.push(String::from("missing issue key")),
+        None => (),
+        Some(issue_key) => {
+            // IssueKey parsing is case insensitive, the prefix is what precedes the key
+            let key_re = Regex::new(&format!("(?i){}", regex::escape(&issue_key.to_string())))
+                .wrap_err("Failed to compile issue key regex")?;
+            let prefix = key_re
+                .find(branch)
+                .map(|m| &branch[..m.start()])
+                .unwrap_or_default();
+            if !cfg.allowed_prefixes.is_empty() && !cfg.allowed_prefixes.iter().any(|p| p == prefix)
+            {
+                violations.push(format!(
+                    "prefix '{prefix}' is not one of: {}",
+                    cfg.allowed_prefixes
+                        .iter()
+                        .map(|p| format!("'{p}'"))
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ));
+            }
+        }
+    }
+
+    if branch.chars().count() > cfg.max_length {
+        violations.push(format!(
+            "longer than {} characters ({})",
+            cfg.max_length,
+            branch.chars().count()
+        ));
+    }
+
+    let sanitized = Repository::sanitize_branch_name(branch);
+    // Sanitizing truncates, length is reported above
+    if !branch.starts_with(&sanitized) || sanitized.is_empty() {
+        violations.push(format!(
+            "contains characters jig branch removes, use '{sanitized}'"
+        ));
+    }
+
+    Ok(violations)
+}
+
+/// Error listing every violation, on a single message as hooks only print the top error
+pub fn rejection(branch: &str, violations: &[String]) -> Report {
+    Report::new(Rejection(format!(
+        "Branch '{branch}' violates the branch naming policy:\n  {}\n  Create branches with: jig branch, or rename with: git branch -m <name>",
+        violations.join("\n  ")
+    )))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn cfg() -> BranchPolicyConfig {
+        BranchPolicyConfig {
+            enabled: true,
+            ..BranchPolicyConfig::from(None)
+        }
+    }
+
+    #[test]
+    fn allowlisted_branches_pass() {
+        assert!(check("main", &cfg()).unwrap().is_empty());
+        assert!(check("release/2.1", &cfg()).unwrap().is_empty());
+        assert_eq!(
+            vec!["missing issue key"],
+            check("mainline", &cfg()).unwrap()
+        );
+    }
+
+    #[test]
+    fn issue_key_and_prefix() {
+        let cfg = BranchPolicyConfig {
+            allowed_prefixes: vec![String::new(), String::from("feature/")],
+            ..cfg()
+        };
+        assert!(check("JB-1_fix_the_thing", &cfg).unwrap().is_empty());
+        assert!(check("feature/JB-1_fix", &cfg).unwrap().is_empty());
+        assert_eq!(
+            vec!["prefix 'bugfix/' is not one of: '', 'feature/'"],
+            check("bugfix/JB-1_fix", &cfg).unwrap()
+        );
+        // Uppercasing changes the length of the prefix
+        assert_eq!(
+            "prefix 'straße/' is not one of: '', 'feature/'",
+            check("straße/jb-1_fix", &cfg).unwrap()[0]
+        );
+    }
+
+    #[test]
+    fn length_and_sanitization() {
+        let cfg = BranchPolicyConfig {
+            max_length: 20,
+            ..cfg()
+        };
+        assert_eq!(
+            vec!["longer than 20 characters (24)"],
+            check("JB-1_fix_the_thing_today", &cfg).unwrap()
+        );
+        assert_eq!(
+            vec!["contains characters jig branch removes, use 'JB-1_fix.it'"],
+            check("JB-1_fix..it", &cfg).unwrap()
+        );
+    }
+
+    #[test]
+    fn violations_are_rejections() {
+        let report = rejection("mainline", &[String::from("missing issue key")]);
+        // reference-transaction fails open on any other error
+        assert!(report.wrap_err("context").root_cause().is::<Rejection>());
+    }
+}
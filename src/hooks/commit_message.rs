@@ -0,0 +1,261 @@
+use regex::Regex;
+use std::{fmt::Display, sync::OnceLock};
+
+static TRAILER_RE: OnceLock<Regex> = OnceLock::new();
+
+/// Commit message split into subject, body, trailers and comments.
+///
+/// The original lines are kept so comments, the scissors line and everything
+/// below it are written back untouched.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommitMessage {
+    lines: Vec<String>,
+    /// None for messages of existing commits, git stripped their comments already
+    comment_char: Option<char>,
+    /// Index of the subject line
+    subject: Option<usize>,
+    /// Index of the scissors line or number of lines, nothing at or after it is part of the message
+    end: usize,
+    trailing_newline: bool,
+}
+
+impl CommitMessage {
+    /// Message being edited, as passed to the commit-msg hook
+    pub fn parse(msg: &str, comment_char: char) -> Self {
+        Self::new(msg, Some(comment_char))
+    }
+
+    /// Message of an existing commit, where lines such as `#123` are content
+    pub fn parse_committed(msg: &str) -> Self {
+        Self::new(msg, None)
+    }
+
+    fn new(msg: &str, comment_char: Option<char>) -> Self {
+        let lines = msg.lines().map(String::from).collect::<Vec<String>>();
+        let end = match comment_char {
+            Some(comment_char) => {
+                let scissors =
+                    format!("{comment_char} ------------------------ >8 ------------------------");
+                lines
+                    .iter()
+                    .position(|l| *l == scissors)
+                    .unwrap_or(lines.len())
+            }
+            None => lines.len(),
+        };
+        let mut msg = CommitMessage {
+            lines,
+            comment_char,
+            subject: None,
+            end,
+            trailing_newline: msg.ends_with('\n'),
+        };
+        msg.subject = msg.lines[..end]
+            .iter()
+            .position(|l| !msg.is_comment(l) && !l.trim().is_empty());
+        msg
+    }
+
+    pub fn is_comment(&self, line: &str) -> bool {
+        self.comment_char.is_some_and(|c| line.starts_with(c))
+    }
+
+    /// Empty if the message only contains comments or whitespace
+    pub fn subject(&self) -> &str {
+        self.subject.map(|i| self.lines[i].as_str()).unwrap_or("")
+    }
+
+    pub fn set_subject(&mut self, subject: String) {
+        match self.subject {
+            Some(i) => self.lines[i] = subject,
+            None => {
+                self.lines.insert(0, subject);
+                self.subject = Some(0);
+                self.end += 1;
+            }
+        }
+    }
+
+    /// Non-comment lines after the subject, including trailers
+    fn content(&self) -> Vec<&str> {
+        let start = self.subject.map(|i| i + 1).unwrap_or(self.end);
+        self.lines[start..self.end]
+            .iter()
+            .filter(|l| !self.is_comment(l))
+            .map(String::as_str)
+            .collect()
+    }
+
+    /// Number of content lines belonging to the trailer block, the last paragraph if all lines are trailers
+    fn trailer_len(&self) -> usize {
+        let trailer_re = TRAILER_RE.get_or_init(|| {
+            Regex::new(r"^[A-Za-z0-9][A-Za-z0-9-]*: \S").expect("Unable to compile TRAILER_RE")
+        });
+
+        let content = self.content();
+        let mut paragraph = content
+            .iter()
+            .rev()
+            .skip_while(|l| l.trim().is_empty())
+            .take_while(|l| !l.trim().is_empty())
+            .collect::<Vec<_>>();
+        paragraph.reverse();
+
+        let is_trailer_block = paragraph.first().is_some_and(|l| trailer_re.is_match(l))
+            && paragraph
+                .iter()
+                .all(|l| trailer_re.is_match(l) || l.starts_with([' ', '\t']));
+        // The body must be separated from the subject by a blank line to contain trailers
+        let separated = content.first().is_some_and(|l| l.trim().is_empty());
+
+        if is_trailer_block && separated {
+            paragraph.len()
+        } else {
+            0
+        }
+    }
+
+    /// False if the line following the subject is not blank
+    pub fn blank_line_after_subject(&self) -> bool {
+        self.content().first().is_none_or(|l| l.trim().is_empty())
+    }
+
+    /// Paragraphs between subject and trailers, surrounding blank lines removed
+    pub fn body(&self) -> Vec<&str> {
+        let content = self.content();
+        let trailer_len = self.trailer_len();
+        let mut body = content[..content.len() - self.trailing_blank_lines() - trailer_len]
+            .iter()
+            .skip_while(|l| l.trim().is_empty())
+            .copied()
+            .collect::<Vec<_>>();
+        while body.last().is_some_and(|l| l.trim().is_empty()) {
+            body.pop();
+        }
+        body
+    }
+
+    pub fn trailers(&self) -> Vec<&str> {
+        let content = self.content();
+        let end = content.len() - self.trailing_blank_lines();
+        content[end - self.trailer_len()..end].to_vec()
+    }
+
+    /// Append a trailer after the last content line, starting a new trailer block if there is none
+    pub fn add_trailer(&mut self, trailer: String) {
+        let block = match self.trailers().is_empty() {
+            true => vec![String::default(), trailer],
+            false => vec![trailer],
+        };
+        let at = self.lines[..self.end]
+            .iter()
+            .rposition(|l| !self.is_comment(l) && !l.trim().is_empty())
+            .map(|i| i + 1)
+            .unwrap_or(self.end);
+
+        self.end += block.len();
+        self.lines.splice(at..at, block);
+    }
+
+    fn trailing_blank_lines(&self) -> usize {
+        self.content()
+            .iter()
+            .rev()
+            .take_while(|l| l.trim().is_empty())
+            .count()
+    }
+}
+
+impl Display for CommitMessage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.lines.join("\n"))?;
+        if self.trailing_newline {
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse_subject_only() {
+        let msg = CommitMessage::parse("Subject\n", '#');
+        assert_eq!("Subject", msg.subject());
+        assert!(msg.body().is_empty());
+        assert!(msg.trailers().is_empty());
+        assert_eq!("Subject\n", msg.to_string());
+    }
+
+    #[test]
+    fn parse_body_and_trailers() {
+        let raw = "Subject\n\nFirst paragraph\n\nSecond paragraph\n\nSigned-off-by: A <a@b.c>\nRefs: JB-1\n";
+        let msg = CommitMessage::parse(raw, '#');
+        assert_eq!("Subject", msg.subject());
+        assert_eq!(vec!["First paragraph", "", "Second paragraph"], msg.body());
+        assert_eq!(
+            vec!["Signed-off-by: A <a@b.c>", "Refs: JB-1"],
+            msg.trailers()
+        );
+        assert_eq!(raw, msg.to_string());
+    }
+
+    #[test]
+    fn parse_trailer_like_body_is_body() {
+        let msg = CommitMessage::parse("Subject\n\nNote: this is prose\nand continues\n", '#');
+        assert_eq!(vec!["Note: this is prose", "and continues"], msg.body());
+        assert!(msg.trailers().is_empty());
+    }
+
+    #[test]
+    fn parse_comments_and_scissors() {
+        let raw = "\n# leading comment\nSubject\n\nBody\n# Please enter the commit message\n# ------------------------ >8 ------------------------\ndiff --git a/x b/x\n";
+        let mut msg = CommitMessage::parse(raw, '#');
+        assert_eq!("Subject", msg.subject());
+        assert_eq!(vec!["Body"], msg.body());
+
+        msg.set_subject(String::from("JB-1 Subject"));
+        assert_eq!(raw.replace("\nSubject", "\nJB-1 Subject"), msg.to_string());
+    }
+
+    #[test]
+    fn add_trailer_to_existing_block() {
+        let mut msg =
+            CommitMessage::parse("Subject\n\nSigned-off-by: A <a@b.c>\n\n# comment\n", '#');
+        msg.add_trailer(String::from("Refs: JB-1"));
+        assert_eq!(
+            "Subject\n\nSigned-off-by: A <a@b.c>\nRefs: JB-1\n\n# comment\n",
+            msg.to_string()
+        );
+    }
+
+    #[test]
+    fn add_trailer_starts_new_block() {
+        let mut msg = CommitMessage::parse("Subject\n# comment\n", '#');
+        msg.add_trailer(String::from("Refs: JB-1"));
+        assert_eq!("Subject\n\nRefs: JB-1\n# comment\n", msg.to_string());
+        assert_eq!(vec!["Refs: JB-1"], msg.trailers());
+    }
+
+    #[test]
+    fn parse_custom_comment_char() {
+        let msg = CommitMessage::parse("; comment\nSubject\n", ';');
+        assert_eq!("Subject", msg.subject());
+    }
+
+    #[test]
+    fn parse_committed_keeps_comment_like_lines() {
+        let msg = CommitMessage::parse_committed("#123 Fix the thing\n\n# Notes\n\nBody\n");
+        assert_eq!("#123 Fix the thing", msg.subject());
+        assert_eq!(vec!["# Notes", "", "Body"], msg.body());
+    }
+
+    #[test]
+    fn parse_only_comments() {
+        let msg = CommitMessage::parse("\n# Please enter the commit message\n", '#');
+        assert_eq!("", msg.subject());
+        assert!(msg.body().is_empty());
+    }
+}
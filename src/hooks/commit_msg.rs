@@ -1,17 +1,30 @@
-use super::lib::Hook;
+use super::{commit_message::CommitMessage, conventional_commit, issue_status, lib::Hook, policy};
 use crate::{
-    config::Config,
-    interactivity::{prompt_user_with_issue_select, query_issues_empty_err},
-    repo::{self, Repository},
+    config::{Config, ExemptCommit, GitHooksConfig, ReferenceStyle},
+    interactivity::prompt_user_with_issue_key_select,
+    repo::{self, CommitInfo, Repository},
 };
 use color_eyre::{
     Result, Section,
     eyre::{WrapErr, eyre},
 };
-use jira::{JiraAPIClient, models::IssueKey};
+use jira::models::IssueKey;
 use regex::Regex;
 use std::{fmt::Display, path::PathBuf};
 
+/// Trailer token used by the trailer reference style
+const REFERENCE_TRAILER: &str = "Refs";
+/// Fall back to cached issues quickly when Jira is unreachable
+const QUERY_TIMEOUT_SECONDS: u64 = 5;
+
+/// Outcome of the commit-msg rules for an existing commit
+#[derive(Debug, Default)]
+pub struct Verification {
+    pub issue_key: Option<IssueKey>,
+    pub failures: Vec<String>,
+    pub warnings: Vec<String>,
+}
+
 #[derive(Debug)]
 pub struct CommitMsg {
     commit_msg_file: PathBuf,
@@ -22,7 +35,292 @@ impl CommitMsg {
     fn write_commit(self, commit_msg: &str) -> Result<()> {
         std::fs::write(self.commit_msg_file, commit_msg).wrap_err("Failed to write new commit_msg")
     }
+
+    /// Kind of commit with a message generated by git, None for regular commits.
+    /// `operation` is the operation git is in the middle of, known from its state files.
+    pub(super) fn exemption(
+        msg: &CommitMessage,
+        operation: Option<ExemptCommit>,
+    ) -> Result<Option<ExemptCommit>> {
+        if operation.is_some() {
+            return Ok(operation);
+        }
+
+        let fixup_commit_re =
+            Regex::new(r"^(squash|fixup|amend)!?").wrap_err("Unable to compile fixup_commit_re")?;
+        let merge_commit_re =
+            Regex::new(r"^Merge (branch|branches|remote-tracking branch|tag|commit|pull request) ")
+                .wrap_err("Unable to compile merge_commit_re")?;
+        let subject = msg.subject();
+        Ok(if fixup_commit_re.is_match(subject) {
+            Some(ExemptCommit::Fixup)
+        } else if subject.starts_with("Revert") {
+            Some(ExemptCommit::Revert)
+        } else if merge_commit_re.is_match(subject) {
+            Some(ExemptCommit::Merge)
+        } else if msg
+            .body()
+            .iter()
+            .any(|line| line.starts_with("(cherry picked from commit "))
+        {
+            Some(ExemptCommit::CherryPick)
+        } else {
+            None
+        })
+    }
+
+    /// Operation git is committing for, from the state files in the git dir
+    fn pending_operation(&self) -> Option<ExemptCommit> {
+        // git notes edit reuses the hook with its own message file
+        if self
+            .commit_msg_file
+            .file_name()
+            .is_some_and(|name| name == "NOTES_EDITMSG")
+        {
+            return Some(ExemptCommit::Notes);
+        }
+
+        [
+            ("MERGE_HEAD", ExemptCommit::Merge),
+            ("CHERRY_PICK_HEAD", ExemptCommit::CherryPick),
+            ("REVERT_HEAD", ExemptCommit::Revert),
+        ]
+        .into_iter()
+        .find(|(state_file, _)| self.repo.in_progress(state_file))
+        .map(|(_, operation)| operation)
+    }
+
+    /// Issue key referenced by the commit message in the configured style.
+    /// Only the subject is searched, except for the `Refs` trailer taking priority in trailer style.
+    pub(super) fn find_issue_key(msg: &CommitMessage, style: ReferenceStyle) -> Option<IssueKey> {
+        let subject_key = IssueKey::try_from(msg.subject().to_string()).ok();
+        if style != ReferenceStyle::Trailer {
+            return subject_key;
+        }
+
+        msg.trailers()
+            .iter()
+            .filter_map(|t| t.strip_prefix(REFERENCE_TRAILER)?.strip_prefix(':'))
+            .find_map(|value| IssueKey::try_from(value.trim().to_string()).ok())
+            .or(subject_key)
+    }
+
+    /// Issue key referenced by an existing commit, merge commits carry the merged branch name,
+    /// e.g. "Merge branch 'JB-12_fix'"
+    pub fn referenced_issue_key(commit: &CommitInfo, style: ReferenceStyle) -> Option<IssueKey> {
+        match commit.parent_count > 1 {
+            true => CommitMsg::merged_issue_key(commit.subject()),
+            false => {
+                CommitMsg::find_issue_key(&CommitMessage::parse_committed(&commit.message), style)
+            }
+        }
+    }
+
+    /// Issue key in the subject of a merge commit. Only uppercase keys count, unlike IssueKey
+    /// parsing, so names like 'release-2024' are not taken for a key.
+    fn merged_issue_key(subject: &str) -> Option<IssueKey> {
+        let merged_key_re = Regex::new(r"(?:^|[^A-Za-z0-9])([A-Z][A-Z0-9_]+-[0-9]+)(?:[^0-9]|$)")
+            .expect("Unable to compile merged_key_re");
+        merged_key_re
+            .captures(subject)
+            .and_then(|c| IssueKey::try_from(c[1].to_string()).ok())
+    }
+
+    /// Decide on the issue key from the branch and the commit message.
+    /// None when neither contain a key and the user has to pick one.
+    fn resolve_issue_key(
+        branch: &str,
+        msg: &CommitMessage,
+        cfg: &GitHooksConfig,
+    ) -> Result<Option<IssueKey>> {
+        let branch_issue_key = IssueKey::try_from(branch.to_string());
+        let commit_issue_key = CommitMsg::find_issue_key(msg, cfg.reference_style);
+
+        match (branch_issue_key, commit_issue_key) {
+            // Fail if keys do not match unless allowed
+            (Ok(bik), Some(cik)) if bik != cik && !cfg.allow_branch_and_commit_msg_mismatch => Err(
+                eyre!("Issue key in commit message does not match '{bik}' in the branch name!",)
+                    .with_note(
+                        || "Enabling 'allow_branch_and_commit_msg_mismatch' will skip this check",
+                    ),
+            ),
+            // Fail if branch is missing issue key unless allowed
+            (Err(_), _) if !cfg.allow_branch_missing_issue_key => {
+                Err(eyre!("Issue key not found in branch name")
+                    .with_suggestion(|| "create a branch using: jig branch")
+                    .with_note(|| "Enabling 'allow_branch_missing_issue_key' will skip this check"))
+            }
+            // Happy path
+            (Ok(bik), None) => Ok(Some(bik)),
+            (_, Some(cik)) => Ok(Some(cik)),
+            // Commit msg should ALWAYS have an issue key, should only be hit if second Failure condition is skipped
+            (Err(_), None) => Ok(None),
+        }
+    }
+
+    /// True for commits the hook would have added an issue key to
+    pub fn is_missing_issue_key(msg: &str, cfg: &GitHooksConfig) -> Result<bool> {
+        let msg = CommitMessage::parse_committed(msg);
+        if msg.subject().is_empty()
+            || CommitMsg::find_issue_key(&msg, cfg.reference_style).is_some()
+        {
+            return Ok(false);
+        }
+        Ok(!CommitMsg::exemption(&msg, None)?.is_some_and(|e| cfg.exempt_commits.contains(&e)))
+    }
+
+    /// Apply the rules of the hook to an existing commit, None for exempt commits.
+    /// Unlike the hook nothing is fixed, a missing or misplaced key is a failure.
+    pub fn verify(
+        msg: &str,
+        parent_count: usize,
+        branch_key: Option<&IssueKey>,
+        cfg: &GitHooksConfig,
+    ) -> Result<Option<Verification>> {
+        let msg = CommitMessage::parse_committed(msg);
+        let operation = (parent_count > 1).then_some(ExemptCommit::Merge);
+        if let Some(exemption) = CommitMsg::exemption(&msg, operation)?
+            && cfg.exempt_commits.contains(&exemption)
+        {
+            return Ok(None);
+        }
+
+        let mut verification = Verification::default();
+        let Some(issue_key) = CommitMsg::find_issue_key(&msg, cfg.reference_style) else {
+            verification
+                .failures
+                .push(String::from("missing issue key"));
+            return Ok(Some(verification));
+        };
+        if let Some(branch_key) = branch_key
+            && *branch_key != issue_key
+            && !cfg.allow_branch_and_commit_msg_mismatch
+        {
+            verification
+                .failures
+                .push(format!("references {issue_key}, branch is {branch_key}"));
+        }
+
+        let commit_msg_re = CommitMsg::commit_msg_re(cfg.reference_style)?;
+        let conforming = match cfg.reference_style {
+            ReferenceStyle::Trailer => msg.trailers().iter().any(|t| commit_msg_re.is_match(t)),
+            _ => commit_msg_re.is_match(msg.subject()),
+        };
+        if !conforming {
+            verification.failures.push(format!(
+                "message not conforming to regex: '{commit_msg_re}'"
+            ));
+        }
+        if cfg.conventional_commits
+            && let Some(failure) = conventional_commit::check_type(msg.subject(), cfg)
+        {
+            verification.failures.push(failure);
+        }
+
+        let description = CommitMsg::strip_issue_key(msg.subject(), &issue_key)?;
+        let description = conventional_commit::strip_type(&description);
+        for violation in policy::check(&msg, description, &cfg.policy)? {
+            match violation.is_error() {
+                true => verification.failures.push(violation.to_string()),
+                false => verification.warnings.push(violation.to_string()),
+            }
+        }
+
+        verification.issue_key = Some(issue_key);
+        Ok(Some(verification))
+    }
+
+    /// Message of an existing commit referencing `issue_key`, rewritten like the hook would
+    pub async fn fix_message(msg: &str, issue_key: &IssueKey, cfg: &Config) -> Result<String> {
+        let mut msg = CommitMessage::parse_committed(msg);
+        CommitMsg::apply_issue_key(&mut msg, issue_key, cfg.hooks_cfg.reference_style)?;
+        if cfg.hooks_cfg.conventional_commits {
+            conventional_commit::apply(&mut msg, issue_key, cfg).await?;
+        }
+        Ok(msg.to_string())
+    }
+
+    /// Subject without the leading or trailing reference to the issue, in any of the reference
+    /// styles. Keys elsewhere in the subject are part of the description.
+    fn strip_issue_key(subject: &str, issue_key: &IssueKey) -> Result<String> {
+        let key = regex::escape(&issue_key.to_string());
+        let reference = format!(r"(\[{key}\]|\({key}\)|{key})");
+        // Whitespace or the end of the subject around the reference, JB-1 is not part of JB-10
+        let leading_re = Regex::new(&format!(r"^\s*{reference}(\s+|$)"))
+            .wrap_err("Unable to compile leading_re")?;
+        let trailing_re = Regex::new(&format!(r"(^|\s+){reference}\s*$"))
+            .wrap_err("Unable to compile trailing_re")?;
+
+        let subject = leading_re.replace(subject, "");
+        Ok(trailing_re.replace(&subject, "").trim().to_string())
+    }
+
+    fn commit_msg_re(style: ReferenceStyle) -> Result<Regex> {
+        let re = match style {
+            ReferenceStyle::Prefix => r"^([A-Z]{2,}-[0-9]+) [A-Z0-9].*",
+            ReferenceStyle::Bracket => r"^\[([A-Z]{2,}-[0-9]+)\] [A-Z0-9].*",
+            ReferenceStyle::Suffix => r"^\S.* \(([A-Z]{2,}-[0-9]+)\)$",
+            ReferenceStyle::Trailer => r"^Refs: ([A-Z]{2,}-[0-9]+)$",
+        };
+        Regex::new(re).wrap_err("Unable to compile commit_msg_re")
+    }
+
+    /// Rewrite the subject line to reference the issue in the configured style.
+    /// Body, trailers and comments are untouched, except for adding the `Refs` trailer.
+    fn apply_issue_key(
+        msg: &mut CommitMessage,
+        issue_key: &IssueKey,
+        style: ReferenceStyle,
+    ) -> Result<()> {
+        // Remove the key in any style and re-add after
+        let mut subject = CommitMsg::strip_issue_key(msg.subject(), issue_key)?;
+
+        let first_char = match subject.chars().nth(0) {
+            Some(c) => c,
+            None => {
+                return Err(
+                    eyre!("Commit message only contains an issue key").with_suggestion(
+                        || "Please write a commit message conveying the intent of the change",
+                    ),
+                );
+            }
+        };
+
+        // Suffix and trailer styles leave the subject as is, e.g. Conventional Commits
+        let capitalize = matches!(style, ReferenceStyle::Prefix | ReferenceStyle::Bracket);
+        if capitalize && first_char.is_ascii_alphabetic() && first_char.is_lowercase() {
+            subject.replace_range(..1, &first_char.to_ascii_uppercase().to_string());
+        }
+
+        let trailer = format!("{REFERENCE_TRAILER}: {issue_key}");
+        let final_subject = match style {
+            ReferenceStyle::Prefix => format!("{issue_key} {subject}"),
+            ReferenceStyle::Bracket => format!("[{issue_key}] {subject}"),
+            ReferenceStyle::Suffix => format!("{subject} ({issue_key})"),
+            ReferenceStyle::Trailer => subject,
+        };
+        msg.set_subject(final_subject);
+        if style == ReferenceStyle::Trailer && !msg.trailers().contains(&trailer.as_str()) {
+            msg.add_trailer(trailer);
+        }
+
+        // Final sanity check
+        let commit_msg_re = CommitMsg::commit_msg_re(style)?;
+        let conforming = match style {
+            ReferenceStyle::Trailer => msg.trailers().iter().any(|t| commit_msg_re.is_match(t)),
+            _ => commit_msg_re.is_match(msg.subject()),
+        };
+        if !conforming {
+            return Err(eyre!(format!(
+                "Commit message not conforming to regex: '{}'",
+                commit_msg_re.to_string()
+            )));
+        }
+
+        Ok(())
+    }
 }
+
 impl Display for CommitMsg {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "{}", Self::hook_name())
@@ -33,24 +331,23 @@ impl Hook for CommitMsg {
     fn hook_name() -> String {
         String::from("commit-msg")
     }
-    fn new() -> CommitMsg {
+    fn new(args: &[String]) -> Result<CommitMsg> {
         let commit_msg_file = PathBuf::from(
-            std::env::args()
-                .nth(1)
-                .expect("Expected commit_msg_file as first argument"),
+            args.first()
+                .ok_or(eyre!("Expected commit_msg_file as first argument"))?,
         );
-        let repo = repo::Repository::open()
-            .wrap_err("Failed to open repository")
-            .unwrap();
+        let repo = repo::Repository::open().wrap_err("Failed to open repository")?;
 
-        CommitMsg {
+        Ok(CommitMsg {
             commit_msg_file,
             repo,
-        }
+        })
     }
 
     async fn exec(self, cfg: &Config) -> Result<()> {
-        let commit_msg = std::fs::read_to_string(self.commit_msg_file.clone()).unwrap();
+        let raw_commit_msg = std::fs::read_to_string(self.commit_msg_file.clone())
+            .wrap_err("Failed to read commit_msg")?;
+        let mut commit_msg = CommitMessage::parse(&raw_commit_msg, self.repo.get_comment_char());
         let branch = self.repo.get_branch_name()?;
 
         // Pre-checks to verify commit should be processed
@@ -60,96 +357,329 @@ impl Hook for CommitMsg {
         } else if branch == *"HEAD" {
             // Compat: rebase operations
             return Ok(());
+        } else if commit_msg.subject().is_empty() {
+            // Compat: git aborts commits with empty messages
+            return Ok(());
         }
-        let fixup_commit_re = Regex::new(r"^(squash|fixup|amend|Revert)!?.*")
-            .wrap_err("Unable to compile fixup_commits_re")?;
-        if fixup_commit_re.captures(&commit_msg).is_some() {
-            // Compat: fixup commits
+        if let Some(exemption) = CommitMsg::exemption(&commit_msg, self.pending_operation())?
+            && cfg.hooks_cfg.exempt_commits.contains(&exemption)
+        {
+            // Compat: merge, revert, cherry-pick and fixup commits keep the message of git
             return Ok(());
         }
 
         // Processing starts
-        let branch_issue_key = IssueKey::try_from(branch.clone());
-        let commit_issue_key = IssueKey::try_from(commit_msg.clone());
-
-        let (issue_key, mut msg) =
-            match (branch_issue_key, commit_issue_key) {
-                // Fail if keys do not match unless allowed
-                (Ok(bik), Ok(cik))
-                    if bik != cik && !cfg.hooks_cfg.allow_branch_and_commit_msg_mismatch =>
-                {
-                    Err(eyre!(
-                        "Issue key in commit message does not match '{bik}' in the branch name!",
-                    )
-                    .with_note(
-                        || "Enabling 'allow_branch_and_commit_msg_mismatch' will skip this check",
-                    ))
-                }
-                // Fail if branch is missing issue key unless allowed
-                (Err(_), _) if !cfg.hooks_cfg.allow_branch_missing_issue_key => {
-                    Err(eyre!("Issue key not found in branch name")
-                        .with_suggestion(|| "create a branch using: jig branch")
-                        .with_note(
-                            || "Enabling 'allow_branch_missing_issue_key' will skip this check",
-                        ))
-                }
-
-                // Happy path
-                (Ok(bik), Err(_)) => Ok((bik, commit_msg)),
-
-                // Remove key from commit message and re-add after
-                (_, Ok(cik)) if commit_msg.starts_with(cik.to_string().as_str()) => {
-                    let mut msg = commit_msg;
-                    msg.replace_range(..cik.to_string().len(), "");
-
-                    Ok((cik, msg.trim().to_string()))
-                }
-                // Key present in msg but incorrect msg format, move key to front
-                (_, Ok(cik)) => {
-                    let msg = commit_msg
-                        .replace(cik.to_string().as_str(), "")
-                        .trim()
-                        .to_string();
-                    Ok((cik, msg))
-                }
-
-                // Commit msg should ALWAYS have an issue key, should only be hit if second Failure condition is skipped
-                (Err(_), Err(_)) => {
-                    let client = JiraAPIClient::new(&cfg.jira_cfg)?;
-                    let issues = query_issues_empty_err(&client, &cfg.issue_query).await?;
-                    let issue_key = prompt_user_with_issue_select(issues)?.key;
-                    Ok((issue_key, commit_msg))
-                }
-            }
-            .with_suggestion(|| "Skip check with: --no-verify")?;
-
-        let first_char = match msg.chars().nth(0) {
-            Some(c) => c,
+        let issue_key = match CommitMsg::resolve_issue_key(&branch, &commit_msg, &cfg.hooks_cfg)
+            .with_suggestion(|| "Skip check with: --no-verify")?
+        {
+            Some(issue_key) => issue_key,
             None => {
-                return Err(
-                    eyre!("Commit message only contains an issue key").with_suggestion(
-                        || "Please write a commit message conveying the intent of the change",
-                    ),
-                );
+                let mut cfg = cfg.to_owned();
+                cfg.jira_cfg.timeout = cfg.jira_cfg.timeout.min(QUERY_TIMEOUT_SECONDS);
+                prompt_user_with_issue_key_select(&cfg).await?
             }
         };
 
-        if first_char.is_ascii_alphabetic() && first_char.is_lowercase() {
-            msg.replace_range(..1, &first_char.to_ascii_uppercase().to_string());
+        if cfg.hooks_cfg.validate_issue_status {
+            issue_status::validate(cfg, &issue_key).await?;
         }
 
-        let commit_msg_re = Regex::new(r"^([A-Z]{2,}-[0-9]+) [A-Z0-9].*")
-            .wrap_err("Unable to compile commit_msg_re")?;
-        let final_msg = format!("{issue_key} {msg}");
+        CommitMsg::apply_issue_key(&mut commit_msg, &issue_key, cfg.hooks_cfg.reference_style)?;
+        if cfg.hooks_cfg.conventional_commits {
+            conventional_commit::apply(&mut commit_msg, &issue_key, cfg).await?;
+        }
 
-        // Final sanity check
-        if !commit_msg_re.is_match(&final_msg) {
-            return Err(eyre!(format!(
-                "Commit message not conforming to regex: '{}'",
-                commit_msg_re.to_string()
-            )));
+        let description = CommitMsg::strip_issue_key(commit_msg.subject(), &issue_key)?;
+        let description = conventional_commit::strip_type(&description);
+        policy::enforce(policy::check(
+            &commit_msg,
+            description,
+            &cfg.hooks_cfg.policy,
+        )?)?;
+        CommitMsg::write_commit(self, &commit_msg.to_string())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::repo::testing::init_repo;
+
+    fn hooks_cfg(reference_style: ReferenceStyle) -> GitHooksConfig {
+        GitHooksConfig {
+            reference_style,
+            ..GitHooksConfig::from(None)
         }
+    }
+
+    fn apply_style(raw: &str, style: ReferenceStyle) -> Result<String> {
+        let mut msg = CommitMessage::parse(raw, '#');
+        let key = CommitMsg::resolve_issue_key("JB-1_branch", &msg, &hooks_cfg(style))?
+            .expect("Branch contains issue key");
+        CommitMsg::apply_issue_key(&mut msg, &key, style)?;
+        Ok(msg.to_string())
+    }
+
+    fn apply(raw: &str) -> Result<String> {
+        apply_style(raw, ReferenceStyle::Prefix)
+    }
+
+    #[test]
+    fn subject_only() {
+        assert_eq!("JB-1 Fix the thing\n", apply("fix the thing\n").unwrap());
+    }
+
+    #[test]
+    fn subject_with_key_prefix() {
+        assert_eq!(
+            "JB-1 Fix the thing\n",
+            apply("JB-1 fix the thing\n").unwrap()
+        );
+    }
+
+    #[test]
+    fn subject_with_key_suffix() {
+        assert_eq!(
+            "JB-1 Fix the thing\n",
+            apply("Fix the thing JB-1\n").unwrap()
+        );
+    }
+
+    #[test]
+    fn body_mentioning_key_is_untouched() {
+        let raw = "Fix the thing\n\nFollow up to JB-1 and JB-2.\n\nSecond paragraph\n";
+        assert_eq!(
+            "JB-1 Fix the thing\n\nFollow up to JB-1 and JB-2.\n\nSecond paragraph\n",
+            apply(raw).unwrap()
+        );
+    }
+
+    #[test]
+    fn comments_are_preserved() {
+        let raw = "fix the thing\n# Please enter the commit message\n# On branch JB-1_branch\n";
+        assert_eq!(
+            "JB-1 Fix the thing\n# Please enter the commit message\n# On branch JB-1_branch\n",
+            apply(raw).unwrap()
+        );
+    }
+
+    #[test]
+    fn verbose_scissors_are_preserved() {
+        let raw = "fix the thing\n\n# ------------------------ >8 ------------------------\n# Do not modify or remove the line above.\ndiff --git a/JB-2 b/JB-2\n";
+        assert_eq!(
+            raw.replacen("fix the thing", "JB-1 Fix the thing", 1),
+            apply(raw).unwrap()
+        );
+    }
+
+    #[test]
+    fn trailers_are_preserved() {
+        let raw = "fix the thing\n\nBody\n\nSigned-off-by: A <a@b.c>\nCo-authored-by: B <b@c.d>\n";
+        assert_eq!(
+            "JB-1 Fix the thing\n\nBody\n\nSigned-off-by: A <a@b.c>\nCo-authored-by: B <b@c.d>\n",
+            apply(raw).unwrap()
+        );
+    }
+
+    #[test]
+    fn missing_issue_keys() {
+        let cfg = GitHooksConfig::from(None);
+        let missing = |raw: &str| CommitMsg::is_missing_issue_key(raw, &cfg).unwrap();
+        assert!(missing("Fix the thing\n\nFor JB-1\n"));
+        assert!(!missing("JB-1 Fix the thing\n"));
+        assert!(!missing("fixup! Fix the thing\n"));
+        assert!(!missing("Merge branch 'main' into JB-1_x\n"));
+        // Not a comment in a committed message
+        assert!(missing("# Fix the heading\n\nJB-1 is next\n"));
+    }
+
+    #[test]
+    fn verify_existing_commits() {
+        let cfg = GitHooksConfig::from(None);
+        let key = IssueKey::try_from(String::from("JB-1")).expect("Valid issue key");
+        let verify = |raw: &str, parent_count: usize| {
+            CommitMsg::verify(raw, parent_count, Some(&key), &cfg)
+                .unwrap()
+                .map(|v| v.failures)
+        };
+
+        assert_eq!(Some(vec![]), verify("JB-1 Fix the thing\n", 1));
+        assert_eq!(None, verify("Merge remote changes\n", 2));
+        assert_eq!(
+            Some(vec![String::from("missing issue key")]),
+            verify("Fix the thing\n", 1)
+        );
+        assert_eq!(
+            Some(vec![
+                String::from("references JB-2, branch is JB-1"),
+                format!(
+                    "message not conforming to regex: '{}'",
+                    CommitMsg::commit_msg_re(ReferenceStyle::Prefix).unwrap()
+                )
+            ]),
+            verify("fix the thing JB-2\n", 1)
+        );
+    }
+
+    #[test]
+    fn merged_issue_keys() {
+        let key = |subject: &str| CommitMsg::merged_issue_key(subject).map(|k| k.to_string());
+        assert_eq!(Some("JB-12"), key("Merge branch 'JB-12_fix'").as_deref());
+        assert_eq!(
+            Some("JB-3"),
+            key("Merge pull request #4 from org/feature/JB-3_docs").as_deref()
+        );
+        assert_eq!(None, key("Merge branch 'release-2024'"));
+        assert_eq!(None, key("Merge branch 'jb-12_fix'"));
+    }
+
+    fn exemption(raw: &str, operation: Option<ExemptCommit>) -> Option<ExemptCommit> {
+        CommitMsg::exemption(&CommitMessage::parse(raw, '#'), operation).unwrap()
+    }
+
+    #[test]
+    fn merge_commits_are_exempt() {
+        assert_eq!(
+            Some(ExemptCommit::Merge),
+            exemption("Merge branch 'JB-2_x' into main\n", None)
+        );
+        assert_eq!(
+            Some(ExemptCommit::Merge),
+            exemption(
+                "Merge pull request #4 from org/feature/JB-3_docs\n\nAdd docs\n",
+                None
+            )
+        );
+        assert_eq!(None, exemption("Merge duplicated settings\n", None));
+    }
+
+    #[test]
+    fn revert_commits_are_exempt() {
+        assert_eq!(
+            Some(ExemptCommit::Revert),
+            exemption(
+                "Revert \"JB-1 Fix the thing\"\n\nThis reverts commit 0123.\n",
+                None
+            )
+        );
+    }
+
+    #[test]
+    fn cherry_picked_commits_are_exempt() {
+        assert_eq!(
+            Some(ExemptCommit::CherryPick),
+            exemption(
+                "Fix the thing\n\n(cherry picked from commit 0123456789abcdef)\n",
+                None
+            )
+        );
+    }
+
+    #[test]
+    fn fixup_commits_are_exempt() {
+        assert_eq!(
+            Some(ExemptCommit::Fixup),
+            exemption("fixup! JB-1 Fix the thing\n", None)
+        );
+        assert_eq!(
+            Some(ExemptCommit::Fixup),
+            exemption("squash! JB-1 Fix the thing\n", None)
+        );
+        assert_eq!(None, exemption("Fix the thing\n", None));
+    }
+
+    #[test]
+    fn pending_operation_from_git_dir() {
+        let dir = init_repo("pending-operation");
+        let git_dir = dir.join(".git");
+        let hook = |msg_file: &str| CommitMsg {
+            commit_msg_file: git_dir.join(msg_file),
+            repo: Repository::open_at(&dir).unwrap(),
+        };
+        assert_eq!(None, hook("COMMIT_EDITMSG").pending_operation());
+        assert_eq!(
+            Some(ExemptCommit::Notes),
+            hook("NOTES_EDITMSG").pending_operation()
+        );
+
+        for (state_file, operation) in [
+            ("MERGE_HEAD", ExemptCommit::Merge),
+            ("CHERRY_PICK_HEAD", ExemptCommit::CherryPick),
+            ("REVERT_HEAD", ExemptCommit::Revert),
+        ] {
+            std::fs::write(git_dir.join(state_file), "0123456789abcdef\n").unwrap();
+            assert_eq!(Some(operation), hook("COMMIT_EDITMSG").pending_operation());
+            std::fs::remove_file(git_dir.join(state_file)).unwrap();
+        }
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn key_prefix_collisions_are_kept() {
+        let key = IssueKey::try_from(String::from("JB-1")).unwrap();
+        let strip = |subject: &str| CommitMsg::strip_issue_key(subject, &key).unwrap();
+        assert_eq!("follow up on JB-10", strip("JB-1 follow up on JB-10"));
+        assert_eq!("JB-10 follow up", strip("JB-10 follow up"));
+        assert_eq!(
+            "fix JB-1 and JB-1",
+            strip("[JB-1] fix JB-1 and JB-1 (JB-1)")
+        );
+        assert_eq!(
+            "JB-1 Follow up on JB-10\n",
+            apply("JB-1 follow up on JB-10\n").unwrap()
+        );
+    }
+
+    #[test]
+    fn key_only_is_rejected() {
+        assert!(apply("JB-1\n# comment\n").is_err());
+    }
+
+    #[test]
+    fn mismatching_key_in_subject_is_rejected() {
+        assert!(apply("JB-2 fix the thing\n").is_err());
+    }
+
+    #[test]
+    fn key_in_comment_is_ignored() {
+        let msg = CommitMessage::parse("fix the thing\n# On branch JB-2_branch\n", '#');
+        let key =
+            CommitMsg::resolve_issue_key("JB-1_branch", &msg, &hooks_cfg(ReferenceStyle::Prefix))
+                .unwrap()
+                .unwrap();
+        assert_eq!("JB-1", key.to_string());
+    }
+
+    #[test]
+    fn bracket_style() {
+        assert_eq!(
+            "[JB-1] Fix the thing\n",
+            apply_style("JB-1 fix the thing\n", ReferenceStyle::Bracket).unwrap()
+        );
+    }
+
+    #[test]
+    fn suffix_style_keeps_conventional_subject() {
+        assert_eq!(
+            "feat: add the thing (JB-1)\n",
+            apply_style("[JB-1] feat: add the thing\n", ReferenceStyle::Suffix).unwrap()
+        );
+    }
+
+    #[test]
+    fn trailer_style_appends_trailer() {
+        let raw = "feat: add the thing JB-1\n\nBody\n\nSigned-off-by: A <a@b.c>\n# comment\n";
+        assert_eq!(
+            "feat: add the thing\n\nBody\n\nSigned-off-by: A <a@b.c>\nRefs: JB-1\n# comment\n",
+            apply_style(raw, ReferenceStyle::Trailer).unwrap()
+        );
+    }
 
-        CommitMsg::write_commit(self, &final_msg)
+    #[test]
+    fn trailer_style_existing_trailer() {
+        let raw = "feat: add the thing\n\nRefs: JB-1\n";
+        assert_eq!(raw, apply_style(raw, ReferenceStyle::Trailer).unwrap());
+        assert!(apply_style("feat: add\n\nRefs: JB-2\n", ReferenceStyle::Trailer).is_err());
     }
 }
@@ -0,0 +1,201 @@
+use super::commit_message::CommitMessage;
+use crate::{
+    cache::{self, Cached},
+    client::JiraClient,
+    config::{Config, GitHooksConfig},
+};
+use color_eyre::{
+    Result, Section,
+    eyre::{WrapErr, eyre},
+    owo_colors::OwoColorize,
+};
+use jira::models::IssueKey;
+use regex::Regex;
+use serde_json::Value;
+use std::{collections::BTreeMap, sync::OnceLock};
+
+static TYPE_RE: OnceLock<Regex> = OnceLock::new();
+
+/// Looking up the issue type should never noticeably delay the commit
+const LOOKUP_TIMEOUT_SECONDS: u64 = 3;
+/// Issue types rarely change, but moving an issue to another type should not take long to apply
+const ISSUE_TYPE_CACHE_SECONDS: i64 = 600;
+const ISSUE_TYPE_CACHE: &str = "issue_types";
+
+type IssueTypeCache = BTreeMap<String, Cached<String>>;
+
+/// Type of a Conventional Commit subject: `type(scope)!: description`
+fn commit_type(subject: &str) -> Option<&str> {
+    let type_re = TYPE_RE.get_or_init(|| {
+        Regex::new(r"^([a-z]+)(\([^()]*\))?!?: \S").expect("Unable to compile TYPE_RE")
+    });
+    type_re
+        .captures(subject)
+        .and_then(|c| c.get(1))
+        .map(|m| m.as_str())
+}
+
+/// Subject without the Conventional Commit type
+pub fn strip_type(subject: &str) -> &str {
+    match commit_type(subject) {
+        Some(_) => subject
+            .split_once(": ")
+            .map(|(_, description)| description)
+            .unwrap_or(subject),
+        None => subject,
+    }
+}
+
+fn validate_type(commit_type: &str, cfg: &GitHooksConfig) -> Result<()> {
+    if cfg
+        .conventional_commit_allowed_types
+        .iter()
+        .any(|t| t == commit_type)
+    {
+        return Ok(());
+    }
+
+    Err(eyre!("Commit type '{commit_type}' is not allowed")
+        .with_note(|| {
+            format!(
+                "Allowed types: {}",
+                cfg.conventional_commit_allowed_types.join(", ")
+            )
+        })
+        .with_suggestion(|| "Update 'conventional_commit_allowed_types' to allow it"))
+}
+
+/// Problem with the commit type of a subject that did not pass through the hook
+pub fn check_type(subject: &str, cfg: &GitHooksConfig) -> Option<String> {
+    match commit_type(subject) {
+        Some(commit_type) => validate_type(commit_type, cfg).err().map(|e| e.to_string()),
+        None => Some(String::from("missing Conventional Commits type")),
+    }
+}
+
+/// Commit type mapped from the issue type, issue type names are case insensitive
+fn type_for_issue(issue_type: &str, cfg: &GitHooksConfig) -> Result<String> {
+    cfg.conventional_commit_types
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case(issue_type))
+        .map(|(_, commit_type)| commit_type.to_owned())
+        .ok_or(eyre!("No commit type mapped for issue type '{issue_type}'"))
+        .with_suggestion(|| {
+            format!("Add the type to the subject or map it in 'conventional_commit_types': {issue_type} = \"<type>\"")
+        })
+}
+
+/// Issue type name, cached per issue key for a series of commits
+async fn issue_type(cfg: &Config, issue_key: &IssueKey) -> Result<String> {
+    let mut cache: IssueTypeCache = cache::read(ISSUE_TYPE_CACHE).unwrap_or_default();
+    if let Some(cached) = cache.get(&issue_key.to_string())
+        && cached.is_fresh(ISSUE_TYPE_CACHE_SECONDS)
+    {
+        return Ok(cached.value.to_owned());
+    }
+
+    let mut cfg = cfg.to_owned();
+    cfg.jira_cfg.timeout = cfg.jira_cfg.timeout.min(LOOKUP_TIMEOUT_SECONDS);
+    let client = JiraClient::new(&cfg)?;
+    let issue = client
+        .get_issue(issue_key, None)
+        .await
+        .wrap_err(format!("Failed to look up issue type of {issue_key}"))?;
+
+    let issue_type = match issue.fields.customfields.get("issuetype") {
+        Some(Value::Object(issue_type)) => issue_type
+            .get("name")
+            .and_then(Value::as_str)
+            .map(String::from),
+        _ => None,
+    }
+    .ok_or(eyre!("Issue {issue_key} does not have an issue type"))?;
+
+    cache.retain(|_, cached| cached.is_fresh(ISSUE_TYPE_CACHE_SECONDS));
+    cache.insert(issue_key.to_string(), Cached::new(issue_type.clone()));
+    // Failing to cache only costs a lookup on the next commit
+    let _ = cache::write(ISSUE_TYPE_CACHE, &cache);
+    Ok(issue_type)
+}
+
+fn prefix_type(msg: &mut CommitMessage, commit_type: &str) {
+    let subject = format!("{commit_type}: {}", msg.subject());
+    msg.set_subject(subject);
+}
+
+/// Validate the commit type of the subject or prefix one derived from the issue type.
+pub async fn apply(msg: &mut CommitMessage, issue_key: &IssueKey, cfg: &Config) -> Result<()> {
+    let hooks_cfg = &cfg.hooks_cfg;
+    if let Some(commit_type) = commit_type(msg.subject()) {
+        return validate_type(commit_type, hooks_cfg);
+    }
+
+    // The type is a convenience, an unreachable Jira should not block the commit
+    let issue_type = match issue_type(cfg, issue_key).await {
+        Ok(issue_type) => issue_type,
+        Err(e) => {
+            eprintln!(
+                "{}",
+                format!(
+                    "Commit type not added, add it to the subject, e.g. 'fix: <subject>': {e:#}"
+                )
+                .bright_yellow()
+            );
+            return Ok(());
+        }
+    };
+    let commit_type = type_for_issue(&issue_type, hooks_cfg)?;
+    validate_type(&commit_type, hooks_cfg)?;
+    prefix_type(msg, &commit_type);
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn hooks_cfg() -> GitHooksConfig {
+        GitHooksConfig {
+            conventional_commits: true,
+            ..GitHooksConfig::from(None)
+        }
+    }
+
+    #[test]
+    fn parse_commit_type() {
+        assert_eq!(Some("feat"), commit_type("feat: add the thing"));
+        assert_eq!(Some("fix"), commit_type("fix(hooks)!: break the thing"));
+        assert_eq!(None, commit_type("Add the thing"));
+        assert_eq!(None, commit_type("Note:add the thing"));
+        // Capitalised words followed by a colon are regular subjects
+        assert_eq!(None, commit_type("Fix: add the thing"));
+        assert_eq!(None, commit_type("WIP: add the thing"));
+        assert_eq!(
+            "break the thing",
+            strip_type("fix(hooks)!: break the thing")
+        );
+        assert_eq!("Add the thing", strip_type("Add the thing"));
+    }
+
+    #[test]
+    fn map_issue_type() {
+        let cfg = hooks_cfg();
+        assert_eq!("fix", type_for_issue("bug", &cfg).unwrap());
+        assert_eq!("feat", type_for_issue("Story", &cfg).unwrap());
+        assert!(type_for_issue("Epic", &cfg).is_err());
+    }
+
+    #[test]
+    fn validate_allowed_types() {
+        let cfg = hooks_cfg();
+        assert!(validate_type("docs", &cfg).is_ok());
+        assert!(validate_type("feature", &cfg).is_err());
+    }
+
+    #[test]
+    fn prefix_keeps_rest_of_message() {
+        let mut msg = CommitMessage::parse("add the thing\n\nBody\n# comment\n", '#');
+        prefix_type(&mut msg, "feat");
+        assert_eq!("feat: add the thing\n\nBody\n# comment\n", msg.to_string());
+    }
+}
@@ -0,0 +1,141 @@
+use crate::{
+    cache::{self, Cached},
+    client::{IssueStatus, JiraClient},
+    config::Config,
+};
+use color_eyre::{
+    Result, Section,
+    eyre::{Report, eyre},
+    owo_colors::OwoColorize,
+};
+use jira::models::IssueKey;
+use std::collections::BTreeMap;
+
+/// Validating the issue should never noticeably delay the commit
+const LOOKUP_TIMEOUT_SECONDS: u64 = 3;
+/// Statuses change during the day, keep lookups for a series of commits only
+const STATUS_CACHE_SECONDS: i64 = 600;
+const ISSUE_STATUS_CACHE: &str = "issue_status";
+
+/// None is cached for issues that do not exist
+type StatusCache = BTreeMap<String, Cached<Option<IssueStatus>>>;
+
+fn check_status(
+    issue_key: &IssueKey,
+    status: Option<&IssueStatus>,
+    allowed_statuses: &[String],
+) -> Result<()> {
+    let Some(status) = status else {
+        return Err(eyre!("Issue {issue_key} does not exist")
+            .with_suggestion(|| "Verify the issue key in the branch name and commit message"));
+    };
+
+    let allowed = match allowed_statuses.is_empty() {
+        true => status.status_category.key != "done",
+        false => allowed_statuses
+            .iter()
+            .any(|s| s.eq_ignore_ascii_case(&status.name)),
+    };
+    if allowed {
+        return Ok(());
+    }
+
+    Err(eyre!("Issue {issue_key} is '{}'", status.name)
+        .with_note(|| match allowed_statuses.is_empty() {
+            true => String::from("Issues in the Done status category are not accepted"),
+            false => format!("Allowed statuses: {}", allowed_statuses.join(", ")),
+        })
+        .with_suggestion(|| "Reopen the issue or reference another issue"))
+}
+
+/// Verify the issue exists and is open, allowing the commit with a warning if Jira is unreachable.
+pub async fn validate(cfg: &Config, issue_key: &IssueKey) -> Result<()> {
+    let mut cache: StatusCache = cache::read(ISSUE_STATUS_CACHE).unwrap_or_default();
+    let allowed_statuses = &cfg.hooks_cfg.allowed_issue_statuses;
+
+    if let Some(cached) = cache.get(&issue_key.to_string())
+        && cached.is_fresh(STATUS_CACHE_SECONDS)
+    {
+        return check_status(issue_key, cached.value.as_ref(), allowed_statuses);
+    }
+
+    let mut cfg = cfg.to_owned();
+    cfg.jira_cfg.timeout = cfg.jira_cfg.timeout.min(LOOKUP_TIMEOUT_SECONDS);
+    let status = match JiraClient::new(&cfg)?.get_issue_status(issue_key).await {
+        Ok(status) => status,
+        Err(e) => {
+            eprintln!(
+                "{}",
+                format!(
+                    "Unable to verify {issue_key} in Jira, allowing commit: {:#}",
+                    Report::new(e)
+                )
+                .bright_yellow()
+            );
+            return Ok(());
+        }
+    };
+
+    cache.retain(|_, cached| cached.is_fresh(STATUS_CACHE_SECONDS));
+    cache.insert(issue_key.to_string(), Cached::new(status.clone()));
+    // Failing to cache only costs a lookup on the next commit
+    let _ = cache::write(ISSUE_STATUS_CACHE, &cache);
+
+    check_status(issue_key, status.as_ref(), allowed_statuses)
+}
+
+/// Verify the issue exists and is open, failing if Jira is unreachable and bypassing the cache
+pub async fn verify_status(
+    client: &JiraClient,
+    issue_key: &IssueKey,
+    allowed_statuses: &[String],
+) -> Result<()> {
+    let status = client.get_issue_status(issue_key).await?;
+    check_status(issue_key, status.as_ref(), allowed_statuses)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::client::StatusCategory;
+
+    fn key() -> IssueKey {
+        IssueKey::try_from(String::from("JB-12")).expect("Valid issue key")
+    }
+
+    fn status(name: &str, category: &str) -> IssueStatus {
+        IssueStatus {
+            name: String::from(name),
+            status_category: StatusCategory {
+                key: String::from(category),
+                name: String::from(name),
+            },
+        }
+    }
+
+    #[test]
+    fn missing_issue_is_rejected() {
+        assert!(check_status(&key(), None, &[]).is_err());
+    }
+
+    #[test]
+    fn done_category_is_rejected() {
+        assert!(check_status(&key(), Some(&status("In Progress", "indeterminate")), &[]).is_ok());
+        assert!(check_status(&key(), Some(&status("Closed", "done")), &[]).is_err());
+    }
+
+    #[test]
+    fn allowed_statuses_override_category() {
+        let allowed = vec![String::from("in review"), String::from("Closed")];
+        assert!(
+            check_status(
+                &key(),
+                Some(&status("In Review", "indeterminate")),
+                &allowed
+            )
+            .is_ok()
+        );
+        assert!(check_status(&key(), Some(&status("Closed", "done")), &allowed).is_ok());
+        assert!(check_status(&key(), Some(&status("To Do", "new")), &allowed).is_err());
+    }
+}
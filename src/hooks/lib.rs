@@ -1,48 +1,272 @@
-use color_eyre::eyre::{Result, eyre};
-use std::{fmt::Display, path::PathBuf};
+use crate::{config, repo::Repository};
+use color_eyre::{
+    eyre::{Report, Result, WrapErr, eyre},
+    owo_colors::OwoColorize,
+};
+use inquire::InquireError;
+use std::{
+    fmt::Display,
+    io::Write,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+};
 
 use super::*;
 
 pub trait Hook: Display {
     fn hook_name() -> String;
-    fn new() -> Self;
+    /// `args` are the arguments git passes to the hook, without the hook path
+    fn new(args: &[String]) -> Result<Self>
+    where
+        Self: Sized;
     async fn exec(self, cfg: &Config) -> Result<()>;
 }
 
-pub fn is_git_hook() -> Result<Option<impl Hook>> {
-    let bin_path = match std::env::args().next() {
+/// Hooks jig can be installed as
+pub const HOOK_NAMES: [&str; 6] = [
+    "commit-msg",
+    "prepare-commit-msg",
+    "pre-push",
+    "post-checkout",
+    "post-merge",
+    "reference-transaction",
+];
+
+/// Hooks git runs, forwarded to the repository while jig is the global core.hooksPath
+pub const GIT_HOOK_NAMES: [&str; 22] = [
+    "applypatch-msg",
+    "pre-applypatch",
+    "post-applypatch",
+    "pre-commit",
+    "pre-merge-commit",
+    "prepare-commit-msg",
+    "commit-msg",
+    "post-commit",
+    "pre-rebase",
+    "post-checkout",
+    "post-merge",
+    "pre-push",
+    "pre-receive",
+    "update",
+    "proc-receive",
+    "post-receive",
+    "post-update",
+    "reference-transaction",
+    "push-to-checkout",
+    "pre-auto-gc",
+    "post-rewrite",
+    "post-index-change",
+];
+
+/// Suffix of existing hooks kept by `jig hook install --chain`, run before jig
+pub const CHAINED_SUFFIX: &str = ".jig-chained";
+
+/// Set for hooks jig runs again in the background, their chained hooks ran in the foreground
+pub(super) const DETACHED_ENV: &str = "JIG_HOOK_DETACHED";
+
+/// A hook refusing the git operation, as opposed to failing to check it
+#[derive(Debug)]
+pub struct Rejection(pub String);
+
+impl Display for Rejection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl std::error::Error for Rejection {}
+
+/// Hooks implemented by jig, dispatched on the name of the executable
+pub enum GitHook {
+    CommitMsg(CommitMsg),
+    PrepareCommitMsg(PrepareCommitMsg),
+    PrePush(PrePush),
+    PostCheckout(PostCheckout),
+    PostMerge(PostMerge),
+    ReferenceTransaction(ReferenceTransaction),
+}
+
+impl GitHook {
+    /// `args` are the arguments git passes to the hook named `name`, None for hooks jig does not implement
+    pub fn new(name: &str, args: &[String]) -> Result<Option<GitHook>> {
+        Ok(match name {
+            "commit-msg" => Some(GitHook::CommitMsg(CommitMsg::new(args)?)),
+            // "applypatch-msg" => None,
+            // "pre-applypatch" => None,
+            // "post-applypatch" => None,
+            // "pre-commit" => None,
+            // "pre-merge-commit" => None,
+            "prepare-commit-msg" => Some(GitHook::PrepareCommitMsg(PrepareCommitMsg::new(args)?)),
+            // "post-commit" => None,
+            // "pre-rebase" => None,
+            "post-checkout" => Some(GitHook::PostCheckout(PostCheckout::new(args)?)),
+            "post-merge" => Some(GitHook::PostMerge(PostMerge::new(args)?)),
+            "pre-push" => Some(GitHook::PrePush(PrePush::new(args)?)),
+            // "pre-receive" => None,
+            // "update" => None,
+            // "proc-receive" => None,
+            // "post-update" => None,
+            "reference-transaction" => Some(GitHook::ReferenceTransaction(
+                ReferenceTransaction::new(args)?,
+            )),
+            // "push-to-checkout" => None,
+            // "pre-auto-gc" => None,
+            // "post-rewrite" => None,
+            // "sendemail-validate" => None,
+            // "fsmonitor-watchman" => None,
+            // "p4-changelist" => None,
+            // "p4-prepare-changelist" => None,
+            // "p4-post-changelist" => None,
+            // "p4-pre-submit" => None,
+            // "post-index-change" => None,
+            _ => None,
+        })
+    }
+
+    /// `args` are passed on to the chained hook
+    pub async fn exec(self, cfg: &Config, args: &[String]) -> Result<()> {
+        if std::env::var_os(DETACHED_ENV).is_none() {
+            self.run_chained(args)?;
+        }
+        match self {
+            GitHook::CommitMsg(hook) => hook.exec(cfg).await,
+            GitHook::PrepareCommitMsg(hook) => hook.exec(cfg).await,
+            GitHook::PrePush(hook) => hook.exec(cfg).await,
+            GitHook::PostCheckout(hook) => hook.exec(cfg).await,
+            GitHook::PostMerge(hook) => hook.exec(cfg).await,
+            GitHook::ReferenceTransaction(hook) => hook.exec(cfg).await,
+        }
+    }
+}
+
+impl GitHook {
+    /// Input git passes on stdin, read by jig before running the chained hook
+    fn stdin(&self) -> Option<&str> {
+        match self {
+            GitHook::PrePush(hook) => Some(&hook.input),
+            GitHook::ReferenceTransaction(hook) => Some(&hook.input),
+            _ => None,
+        }
+    }
+
+    /// Run the hook replaced by jig, and the hook of the repository when jig is installed
+    /// globally, with the same arguments and input
+    fn run_chained(&self, args: &[String]) -> Result<()> {
+        run_chained(&Repository::open()?, &self.to_string(), self.stdin(), args)
+    }
+}
+
+/// Run the hooks chained to the hook `name` installed in the hooks path of `repo`
+pub(crate) fn run_chained(
+    repo: &Repository,
+    name: &str,
+    input: Option<&str>,
+    args: &[String],
+) -> Result<()> {
+    let hooks_path = repo.get_hooks_path()?;
+    let chained = hooks_path.join(format!("{name}{CHAINED_SUFFIX}"));
+    if chained.exists() {
+        run_script(&chained, input, args)?;
+    }
+    if hooks_path == config::global_hooks_dir() {
+        run_forwarded(repo, name, input, args)?;
+    }
+    Ok(())
+}
+
+/// Run the hooks git ignores because of the global jig hooks path, see `forwarded_hooks`
+pub(crate) fn run_forwarded(
+    repo: &Repository,
+    name: &str,
+    input: Option<&str>,
+    args: &[String],
+) -> Result<()> {
+    for hook in forwarded_hooks(repo, name) {
+        run_script(&hook, input, args)?;
+    }
+    Ok(())
+}
+
+fn run_script(hook: &Path, input: Option<&str>, args: &[String]) -> Result<()> {
+    let mut child = Command::new(hook)
+        .args(args)
+        .stdin(match input {
+            Some(_) => Stdio::piped(),
+            None => Stdio::inherit(),
+        })
+        .spawn()
+        .wrap_err(format!("Failed to run hook {}", hook.display()))?;
+    if let (Some(input), Some(mut stdin)) = (input, child.stdin.take()) {
+        // The hook is free to ignore its input
+        let _ = stdin.write_all(input.as_bytes());
+    }
+
+    let status = child.wait()?;
+    match status.success() {
+        true => Ok(()),
+        false => Err(Report::new(Rejection(format!(
+            "Hook {} failed ({status})",
+            hook.display()
+        )))),
+    }
+}
+
+impl Display for GitHook {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GitHook::CommitMsg(hook) => hook.fmt(f),
+            GitHook::PrepareCommitMsg(hook) => hook.fmt(f),
+            GitHook::PrePush(hook) => hook.fmt(f),
+            GitHook::PostCheckout(hook) => hook.fmt(f),
+            GitHook::PostMerge(hook) => hook.fmt(f),
+            GitHook::ReferenceTransaction(hook) => hook.fmt(f),
+        }
+    }
+}
+
+/// The hook jig is invoked as through a symlink, with the arguments git passed to it
+pub fn is_git_hook() -> Result<Option<(GitHook, Vec<String>)>> {
+    let args = std::env::args().collect::<Vec<_>>();
+    let bin_path = match args.first() {
         Some(p) => PathBuf::from(p),
         None => Err(eyre!("Unable to get first arg: path of current executable"))?,
     };
 
-    Ok(match bin_path.file_name().unwrap().to_str() {
-        Some("commit-msg") => Some(CommitMsg::new()),
-        // Some("applypatch-msg") => None,
-        // Some("pre-applypatch") => None,
-        // Some("post-applypatch") => None,
-        // Some("pre-commit") => None,
-        // Some("pre-merge-commit") => None,
-        // Some("prepare-commit-msg") => None,
-        // Some("post-commit") => None,
-        // Some("pre-rebase") => None,
-        // Some("post-checkout") => None,
-        // Some("post-merge") => None,
-        // Some("pre-push") => None,
-        // Some("pre-receive") => None,
-        // Some("update") => None,
-        // Some("proc-receive") => None,
-        // Some("post-update") => None,
-        // Some("reference-transaction") => None,
-        // Some("push-to-checkout") => None,
-        // Some("pre-auto-gc") => None,
-        // Some("post-rewrite") => None,
-        // Some("sendemail-validate") => None,
-        // Some("fsmonitor-watchman") => None,
-        // Some("p4-changelist") => None,
-        // Some("p4-prepare-changelist") => None,
-        // Some("p4-post-changelist") => None,
-        // Some("p4-pre-submit") => None,
-        // Some("post-index-change") => None,
-        _ => None,
-    })
+    let Some(name) = bin_path.file_name().and_then(|name| name.to_str()) else {
+        return Ok(None);
+    };
+    Ok(GitHook::new(name, &args[1..])?.map(|hook| (hook, args[1..].to_vec())))
+}
+
+/// Run the hook, on failure print the error and exit with status 1 like any failing hook
+pub async fn run(hook: GitHook, cfg: impl FnOnce() -> Result<Config>, args: &[String]) {
+    // Runs three times for every ref update, only the prepared state can abort it
+    if let GitHook::ReferenceTransaction(hook) = &hook
+        && !hook.is_prepared()
+    {
+        return;
+    }
+    // Failing reference-transaction aborts every ref update, only rejections may fail it
+    let fail_open = matches!(hook, GitHook::ReferenceTransaction(_));
+
+    let res = match cfg() {
+        Ok(cfg) => hook.exec(&cfg, args).await,
+        // The policy is off without config
+        Err(_) if fail_open => hook.run_chained(args),
+        Err(e) => Err(e),
+    };
+    if let Err(e) = res {
+        if fail_open && !e.root_cause().is::<Rejection>() {
+            eprintln!(
+                "{}",
+                format!("Branch naming policy not checked: {e:#}").bright_yellow()
+            );
+            return;
+        }
+        match e.root_cause().downcast_ref::<InquireError>() {
+            Some(InquireError::OperationInterrupted) | Some(InquireError::OperationCanceled) => (),
+            _ => eprintln!("{}", format!("Jig githook error:\n   {e}").bright_red()),
+        }
+        std::process::exit(1);
+    }
 }
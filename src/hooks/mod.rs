@@ -1,6 +1,26 @@
+mod branch_policy;
+mod commit_message;
 mod commit_msg;
+mod conventional_commit;
+mod issue_status;
 mod lib;
+mod policy;
+mod post_checkout;
+mod post_merge;
+mod pre_push;
+mod prepare_commit_msg;
+mod reference_transaction;
+mod state;
 
 use crate::config::Config;
 pub use commit_msg::CommitMsg;
+pub use issue_status::verify_status;
 pub use lib::*;
+pub use post_checkout::PostCheckout;
+pub use post_merge::PostMerge;
+pub use pre_push::PrePush;
+pub use prepare_commit_msg::PrepareCommitMsg;
+pub use reference_transaction::ReferenceTransaction;
+pub use state::{
+    FORWARD_MARKER, HOOKS_PATH_BACKUP, HookState, SHIM_MARKER, forwarded_hooks, jig_in_path,
+};
@@ -0,0 +1,276 @@
+use super::commit_message::CommitMessage;
+use crate::config::{PolicyConfig, RuleLevel};
+use color_eyre::{
+    Result,
+    eyre::{WrapErr, eyre},
+};
+use regex::Regex;
+use std::fmt::Display;
+
+/// Verbs whose base form ends like a past tense, gerund or third person verb
+const IMPERATIVE_EXCEPTIONS: [&str; 13] = [
+    "alias", "bring", "embed", "exceed", "feed", "need", "ping", "proceed", "seed", "shed",
+    "speed", "string", "succeed",
+];
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct Violation {
+    rule: &'static str,
+    level: RuleLevel,
+    message: String,
+}
+
+impl Display for Violation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let level = match self.level {
+            RuleLevel::Error => "error",
+            RuleLevel::Warn => "warn",
+            RuleLevel::Off => "off",
+        };
+        write!(f, "{level}[{}]: {}", self.rule, self.message)
+    }
+}
+
+impl Violation {
+    pub fn is_error(&self) -> bool {
+        self.level == RuleLevel::Error
+    }
+}
+
+/// Collects violations of rules that are not turned off
+#[derive(Default)]
+struct Violations {
+    violations: Vec<Violation>,
+}
+
+impl Violations {
+    fn check(
+        &mut self,
+        rule: &'static str,
+        level: RuleLevel,
+        violation: impl FnOnce() -> Option<String>,
+    ) {
+        if level == RuleLevel::Off {
+            return;
+        }
+        if let Some(message) = violation() {
+            self.violations.push(Violation {
+                rule,
+                level,
+                message,
+            });
+        }
+    }
+}
+
+fn is_imperative(word: &str) -> bool {
+    let word = word.to_lowercase();
+    if IMPERATIVE_EXCEPTIONS.contains(&word.as_str()) {
+        return true;
+    }
+    let third_person = word.ends_with('s') && !word.ends_with("ss") && !word.ends_with("us");
+    !(word.ends_with("ed") || word.ends_with("ing") || third_person)
+}
+
+/// Check the commit message against the policy rules.
+/// `description` is the subject without issue reference and commit type.
+pub fn check(msg: &CommitMessage, description: &str, cfg: &PolicyConfig) -> Result<Vec<Violation>> {
+    let mut v = Violations::default();
+    let subject = msg.subject();
+
+    v.check("subject-max-length", cfg.subject_max_length, || {
+        let length = subject.chars().count();
+        (length > cfg.subject_max_length_limit).then(|| {
+            format!(
+                "Subject is {length} characters, limit is {}",
+                cfg.subject_max_length_limit
+            )
+        })
+    });
+
+    let first_word = description.split_whitespace().next().unwrap_or_default();
+    v.check("imperative-mood", cfg.imperative_mood, || {
+        (!is_imperative(first_word)).then(|| {
+            format!("Subject should start with an imperative verb, e.g. 'Add' not '{first_word}'")
+        })
+    });
+
+    v.check(
+        "subject-trailing-period",
+        cfg.subject_trailing_period,
+        || {
+            description
+                .ends_with('.')
+                .then(|| String::from("Subject should not end with a period"))
+        },
+    );
+
+    v.check("body-blank-line", cfg.body_blank_line, || {
+        (!msg.blank_line_after_subject())
+            .then(|| String::from("Subject and body should be separated by a blank line"))
+    });
+
+    let body = msg.body();
+    v.check("body-wrap-width", cfg.body_wrap_width, || {
+        // Lines without whitespace, e.g. links, can not be wrapped
+        let long_lines = body
+            .iter()
+            .enumerate()
+            .filter(|(_, l)| l.chars().count() > cfg.body_wrap_width_limit)
+            .filter(|(_, l)| l.trim().contains(char::is_whitespace))
+            .map(|(i, _)| (i + 1).to_string())
+            .collect::<Vec<_>>();
+        (!long_lines.is_empty()).then(|| {
+            format!(
+                "Body lines {} exceed {} characters",
+                long_lines.join(", "),
+                cfg.body_wrap_width_limit
+            )
+        })
+    });
+
+    // Invalid patterns only fail commits while the rule is on
+    let deny_regexes = match cfg.deny_regex {
+        RuleLevel::Off => vec![],
+        _ => cfg
+            .deny_regex_patterns
+            .iter()
+            .map(|p| Regex::new(p).wrap_err(format!("Invalid deny_regex_patterns entry: '{p}'")))
+            .collect::<Result<Vec<_>>>()?,
+    };
+    let content = [vec![subject], body.clone()].concat();
+    v.check("deny-regex", cfg.deny_regex, || {
+        let matched = deny_regexes
+            .iter()
+            .filter(|re| content.iter().any(|l| re.is_match(l)))
+            .map(|re| format!("'{re}'"))
+            .collect::<Vec<_>>();
+        (!matched.is_empty()).then(|| {
+            format!(
+                "Commit message matches denied pattern {}",
+                matched.join(", ")
+            )
+        })
+    });
+
+    Ok(v.violations)
+}
+
+/// Print warnings and fail if any rule set to error is violated
+pub fn enforce(violations: Vec<Violation>) -> Result<()> {
+    let errors = violations
+        .iter()
+        .filter(|v| v.level == RuleLevel::Error)
+        .count();
+    let report = violations
+        .iter()
+        .map(|v| format!("  {v}"))
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    match (errors, violations.is_empty()) {
+        (_, true) => Ok(()),
+        (0, false) => {
+            eprintln!("Commit message policy warnings:\n{report}");
+            Ok(())
+        }
+        _ => Err(eyre!(
+            "Commit message violates {errors} policy rule(s):\n{report}\n  Set rules to 'warn' or 'off' under [git_hooks.policy] or skip with: --no-verify"
+        )),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn all_errors() -> PolicyConfig {
+        PolicyConfig {
+            subject_max_length: RuleLevel::Error,
+            subject_max_length_limit: 30,
+            imperative_mood: RuleLevel::Error,
+            subject_trailing_period: RuleLevel::Error,
+            body_blank_line: RuleLevel::Error,
+            body_wrap_width: RuleLevel::Error,
+            body_wrap_width_limit: 20,
+            deny_regex: RuleLevel::Error,
+            deny_regex_patterns: vec![String::from(r"\bWIP\b")],
+        }
+    }
+
+    fn rules(raw: &str, description: &str, cfg: &PolicyConfig) -> Vec<&'static str> {
+        let msg = CommitMessage::parse(raw, '#');
+        check(&msg, description, cfg)
+            .unwrap()
+            .into_iter()
+            .map(|v| v.rule)
+            .collect()
+    }
+
+    #[test]
+    fn conforming_message() {
+        let raw = "JB-1 Add the thing\n\nShort body\nhttps://example.com/a/very/long/link\n";
+        assert!(rules(raw, "Add the thing", &all_errors()).is_empty());
+    }
+
+    #[test]
+    fn all_violations_reported() {
+        let raw =
+            "JB-1 Added the WIP thing, with a long subject.\nBody line that is far too long\n";
+        assert_eq!(
+            vec![
+                "subject-max-length",
+                "imperative-mood",
+                "subject-trailing-period",
+                "body-blank-line",
+                "body-wrap-width",
+                "deny-regex",
+            ],
+            rules(
+                raw,
+                "Added the WIP thing, with a long subject.",
+                &all_errors()
+            )
+        );
+    }
+
+    #[test]
+    fn rules_turned_off() {
+        let cfg = PolicyConfig::from(None);
+        let raw = "JB-1 Added the WIP thing.\nBody\n";
+        assert!(rules(raw, "Added the WIP thing.", &cfg).is_empty());
+    }
+
+    #[test]
+    fn invalid_deny_pattern() {
+        let msg = CommitMessage::parse("JB-1 Add the thing\n", '#');
+        let mut cfg = PolicyConfig {
+            deny_regex_patterns: vec![String::from("WIP(")],
+            ..PolicyConfig::from(None)
+        };
+        assert!(check(&msg, "Add the thing", &cfg).unwrap().is_empty());
+
+        cfg.deny_regex = RuleLevel::Warn;
+        assert!(check(&msg, "Add the thing", &cfg).is_err());
+    }
+
+    #[test]
+    fn imperative_mood() {
+        assert!(is_imperative("Add"));
+        assert!(is_imperative("Process"));
+        assert!(is_imperative("Bring"));
+        assert!(!is_imperative("Fixes"));
+        assert!(!is_imperative("Adding"));
+        assert!(!is_imperative("fixed"));
+    }
+
+    #[test]
+    fn warnings_do_not_fail() {
+        let violations = vec![Violation {
+            rule: "imperative-mood",
+            level: RuleLevel::Warn,
+            message: String::from("Message"),
+        }];
+        assert!(enforce(violations).is_ok());
+    }
+}
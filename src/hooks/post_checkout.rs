@@ -0,0 +1,147 @@
+use super::lib::{DETACHED_ENV, Hook};
+use crate::{
+    client::{IssueStatus, JiraClient},
+    commands::Transition,
+    config::Config,
+    repo::{self, Repository},
+};
+use color_eyre::{Result, eyre::WrapErr, owo_colors::OwoColorize};
+use jira::models::IssueKey;
+use std::{
+    fmt::Display,
+    process::{Command, Stdio},
+};
+
+#[derive(Debug)]
+pub struct PostCheckout {
+    /// 1 for branch checkouts, 0 for file checkouts
+    branch_checkout: bool,
+    /// Passed on to the detached process
+    args: Vec<String>,
+    repo: Repository,
+}
+
+impl Display for PostCheckout {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", Self::hook_name())
+    }
+}
+
+impl PostCheckout {
+    /// Only issues nobody started on, never move issues backwards
+    fn is_unstarted(status: Option<&IssueStatus>) -> bool {
+        status.is_some_and(|s| s.status_category.key == "new")
+    }
+
+    /// Move the issue out of To Do and assign it, returns what was done
+    async fn start_work(cfg: &Config, issue_key: &IssueKey) -> Result<Vec<String>> {
+        let hooks_cfg = &cfg.hooks_cfg;
+        let client = JiraClient::new(cfg)?;
+
+        if !Self::is_unstarted(client.get_issue_status(issue_key).await?.as_ref()) {
+            return Ok(vec![]);
+        }
+
+        let mut done = vec![];
+        if let Some(transition) = &hooks_cfg.post_checkout_transition {
+            Transition::move_to(&client, issue_key, transition).await?;
+            done.push(format!("moved to {transition}"));
+        }
+        if hooks_cfg.post_checkout_assign {
+            let user = client.get_myself().await?;
+            client.post_assign_user(issue_key, &user).await?;
+            done.push(format!("assigned to {}", user.display_name));
+        }
+        Ok(done)
+    }
+
+    /// Run the hook again in the background, so git checkout never waits for Jira
+    fn detach(&self) -> Result<()> {
+        let mut command = Command::new(std::env::current_exe()?);
+        command
+            .args(["hook", "run", &Self::hook_name()])
+            .args(&self.args)
+            .env(DETACHED_ENV, "1")
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null());
+        // Outlive the terminal's Ctrl+C
+        #[cfg(unix)]
+        std::os::unix::process::CommandExt::process_group(&mut command, 0);
+        command
+            .spawn()
+            .wrap_err("Failed to start jig in the background")?;
+        Ok(())
+    }
+}
+
+impl Hook for PostCheckout {
+    fn hook_name() -> String {
+        String::from("post-checkout")
+    }
+
+    fn new(args: &[String]) -> Result<PostCheckout> {
+        let branch_checkout = args.get(2).is_some_and(|flag| flag == "1");
+        let repo = repo::Repository::open().wrap_err("Failed to open repository")?;
+
+        Ok(PostCheckout {
+            branch_checkout,
+            args: args.to_vec(),
+            repo,
+        })
+    }
+
+    /// Never fails, the exit code of post-checkout becomes the exit code of git checkout
+    async fn exec(self, cfg: &Config) -> Result<()> {
+        let hooks_cfg = &cfg.hooks_cfg;
+        if !self.branch_checkout
+            || (hooks_cfg.post_checkout_transition.is_none() && !hooks_cfg.post_checkout_assign)
+        {
+            return Ok(());
+        }
+        let Ok(issue_key) = IssueKey::try_from(self.repo.get_branch_name()?) else {
+            return Ok(());
+        };
+
+        if std::env::var_os(DETACHED_ENV).is_none() {
+            if let Err(e) = self.detach() {
+                eprintln!(
+                    "{}",
+                    format!("Unable to start work on {issue_key}: {e:#}").bright_yellow()
+                );
+            }
+            return Ok(());
+        }
+
+        // Nobody is watching the output of the detached process
+        let _ = Self::start_work(cfg, &issue_key).await;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::client::StatusCategory;
+
+    fn status(name: &str, category: &str) -> IssueStatus {
+        IssueStatus {
+            name: String::from(name),
+            status_category: StatusCategory {
+                key: String::from(category),
+                name: String::from(name),
+            },
+        }
+    }
+
+    #[test]
+    fn only_unstarted_issues_are_moved() {
+        assert!(PostCheckout::is_unstarted(Some(&status("To Do", "new"))));
+        assert!(!PostCheckout::is_unstarted(Some(&status(
+            "In Progress",
+            "indeterminate"
+        ))));
+        assert!(!PostCheckout::is_unstarted(Some(&status("Done", "done"))));
+        assert!(!PostCheckout::is_unstarted(None));
+    }
+}
@@ -0,0 +1,161 @@
+use super::{commit_msg::CommitMsg, lib::Hook};
+use crate::{
+    client::JiraClient,
+    commands::Transition,
+    config::{Config, ReferenceStyle},
+    repo::{self, CommitInfo, Repository},
+};
+use color_eyre::{Result, eyre::WrapErr, owo_colors::OwoColorize};
+use inquire::MultiSelect;
+use jira::models::{IssueKey, PostCommentBody};
+use std::fmt::Display;
+
+/// Merges are interactive already, but an unreachable Jira should not hang them
+const QUERY_TIMEOUT_SECONDS: u64 = 5;
+
+#[derive(Debug)]
+pub struct PostMerge {
+    /// 1 for squash merges, which create no commit
+    squash: bool,
+    repo: Repository,
+}
+
+impl Display for PostMerge {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", Self::hook_name())
+    }
+}
+
+impl PostMerge {
+    /// Issue keys referenced by the merged commits, in order of first appearance
+    fn issue_keys(commits: &[CommitInfo], style: ReferenceStyle) -> Vec<IssueKey> {
+        let mut keys = vec![];
+        for commit in commits {
+            if let Some(key) = CommitMsg::referenced_issue_key(commit, style)
+                && !keys.contains(&key)
+            {
+                keys.push(key);
+            }
+        }
+        keys
+    }
+
+    async fn transition(
+        client: &JiraClient,
+        issue_key: &IssueKey,
+        transition: &str,
+        comment: &str,
+    ) -> Result<()> {
+        Transition::move_to(client, issue_key, transition).await?;
+        client
+            .post_comment(
+                issue_key,
+                PostCommentBody {
+                    body: comment.to_string(),
+                },
+            )
+            .await
+            .wrap_err("Posting comment failed")?;
+        Ok(())
+    }
+}
+
+impl Hook for PostMerge {
+    fn hook_name() -> String {
+        String::from("post-merge")
+    }
+
+    fn new(args: &[String]) -> Result<PostMerge> {
+        let squash = args.first().is_some_and(|flag| flag == "1");
+        let repo = repo::Repository::open().wrap_err("Failed to open repository")?;
+
+        Ok(PostMerge { squash, repo })
+    }
+
+    /// The merge is done, failures are reported as warnings
+    async fn exec(self, cfg: &Config) -> Result<()> {
+        let hooks_cfg = &cfg.hooks_cfg;
+        let Some(transition) = &hooks_cfg.post_merge_transition else {
+            return Ok(());
+        };
+        let branch = self.repo.get_branch_name()?;
+        // Squash merges are picked up by the commit that follows
+        if self.squash || !hooks_cfg.post_merge_branches.contains(&branch) {
+            return Ok(());
+        }
+        // Pulls merge the remote branch into the local copy, the issues were merged already
+        if std::env::var("GIT_REFLOG_ACTION").is_ok_and(|action| action.starts_with("pull")) {
+            return Ok(());
+        }
+
+        // ORIG_HEAD is the tip of the branch before the merge
+        let orig_head = self.repo.rev_parse("ORIG_HEAD")?;
+        let commits = self.repo.get_commits("HEAD", &[orig_head])?;
+        // Fast-forwards create no merge commit to reference
+        let Some(merge_commit) = commits.first().filter(|commit| commit.parent_count > 1) else {
+            return Ok(());
+        };
+        let issue_keys = Self::issue_keys(&commits, hooks_cfg.reference_style);
+        if issue_keys.is_empty() {
+            return Ok(());
+        }
+
+        let selected =
+            match MultiSelect::new(&format!("Move merged issues to {transition}:"), issue_keys)
+                .prompt()
+            {
+                Ok(selected) => selected,
+                Err(e) => {
+                    eprintln!(
+                        "{}",
+                        format!("Skipping Jira transitions: {e}").bright_yellow()
+                    );
+                    return Ok(());
+                }
+            };
+
+        let mut cfg = cfg.to_owned();
+        cfg.jira_cfg.timeout = cfg.jira_cfg.timeout.min(QUERY_TIMEOUT_SECONDS);
+        let client = JiraClient::new(&cfg)?;
+        let comment = format!("Merged to {branch} in {}", merge_commit.id);
+        for issue_key in selected {
+            match Self::transition(&client, &issue_key, transition, &comment).await {
+                Ok(()) => println!("{issue_key}: moved to {transition}"),
+                Err(e) => eprintln!(
+                    "{}",
+                    format!("Unable to move {issue_key} to {transition}: {e:#}").bright_yellow()
+                ),
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn commit(message: &str, parent_count: usize) -> CommitInfo {
+        CommitInfo {
+            id: String::from("0123456789abcdef"),
+            message: String::from(message),
+            parent_count,
+        }
+    }
+
+    #[test]
+    fn issue_keys_from_merged_commits() {
+        let commits = [
+            commit("Merge branch 'JB-3_docs' into main\n", 2),
+            commit("JB-1 Fix the thing\n", 1),
+            commit("JB-1 Test the thing\n", 1),
+            commit("Update readme\n", 1),
+            commit("JB-2 Add the thing\n", 1),
+        ];
+        let keys = PostMerge::issue_keys(&commits, ReferenceStyle::Prefix)
+            .iter()
+            .map(|k| k.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(vec!["JB-3", "JB-1", "JB-2"], keys);
+    }
+}
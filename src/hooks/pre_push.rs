@@ -0,0 +1,222 @@
+use super::{branch_policy, commit_msg::CommitMsg, lib::Hook};
+use crate::{
+    config::{Config, GitHooksConfig},
+    repo::{self, CommitInfo, Repository},
+};
+use color_eyre::{
+    Result,
+    eyre::{WrapErr, eyre},
+};
+use jira::models::IssueKey;
+use std::fmt::Display;
+
+#[derive(Debug)]
+pub struct PrePush {
+    /// Name of the remote, or its url if the push is not to a named remote
+    remote: String,
+    /// Ref updates read from stdin
+    pub(super) input: String,
+    repo: Repository,
+}
+
+/// Line read from stdin: `<local ref> <local sha> <remote ref> <remote sha>`
+#[derive(Debug, PartialEq, Eq)]
+struct RefUpdate {
+    local_ref: String,
+    local_sha: String,
+    remote_sha: String,
+}
+
+impl Display for PrePush {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", Self::hook_name())
+    }
+}
+
+pub(super) fn is_zero(sha: &str) -> bool {
+    sha.chars().all(|c| c == '0')
+}
+
+impl PrePush {
+    fn parse_updates(input: &str) -> Vec<RefUpdate> {
+        input
+            .lines()
+            .filter_map(
+                |line| match line.split_whitespace().collect::<Vec<_>>()[..] {
+                    [local_ref, local_sha, _remote_ref, remote_sha] => Some(RefUpdate {
+                        local_ref: local_ref.to_string(),
+                        local_sha: local_sha.to_string(),
+                        remote_sha: remote_sha.to_string(),
+                    }),
+                    _ => None,
+                },
+            )
+            .collect()
+    }
+
+    /// Reasons the commit is rejected by the commit-msg rules, None if it passes or is exempt
+    fn violation(
+        commit: &CommitInfo,
+        branch_key: Option<&IssueKey>,
+        cfg: &GitHooksConfig,
+    ) -> Result<Option<String>> {
+        Ok(
+            CommitMsg::verify(&commit.message, commit.parent_count, branch_key, cfg)?
+                .filter(|verification| !verification.failures.is_empty())
+                .map(|verification| verification.failures.join(", ")),
+        )
+    }
+}
+
+impl Hook for PrePush {
+    fn hook_name() -> String {
+        String::from("pre-push")
+    }
+
+    fn new(args: &[String]) -> Result<PrePush> {
+        let remote = args
+            .first()
+            .cloned()
+            .ok_or(eyre!("Expected remote name as first argument"))?;
+        let input = std::io::read_to_string(std::io::stdin()).wrap_err("Failed to read stdin")?;
+        let repo = repo::Repository::open().wrap_err("Failed to open repository")?;
+
+        Ok(PrePush {
+            remote,
+            input,
+            repo,
+        })
+    }
+
+    async fn exec(self, cfg: &Config) -> Result<()> {
+        // Commits already on the remote have been verified by whoever pushed them.
+        // Pushes to a url have no remote-tracking branches, any remote's commits are known.
+        let mut remote_tips = self.repo.get_remote_tips(&self.remote)?;
+        if remote_tips.is_empty() {
+            remote_tips = self.repo.get_all_remote_tips()?;
+        }
+
+        let mut rejected = vec![];
+        for update in Self::parse_updates(&self.input) {
+            // Deletions and tags carry no new commits to verify
+            let Some(branch) = update.local_ref.strip_prefix("refs/heads/") else {
+                continue;
+            };
+            if is_zero(&update.local_sha) {
+                continue;
+            }
+
+            // Fallback for git versions without the reference-transaction hook
+            if cfg.hooks_cfg.branch_policy.enabled && is_zero(&update.remote_sha) {
+                let violations = branch_policy::check(branch, &cfg.hooks_cfg.branch_policy)?;
+                if !violations.is_empty() {
+                    return Err(branch_policy::rejection(branch, &violations));
+                }
+            }
+
+            // Branches without a key, e.g. main after merging locally, only need keyed commits
+            let branch_key = IssueKey::try_from(branch.to_string()).ok();
+
+            let mut hidden = remote_tips.clone();
+            if !is_zero(&update.remote_sha) {
+                hidden.push(update.remote_sha);
+            }
+            for commit in self.repo.get_commits(&update.local_sha, &hidden)? {
+                if let Some(reason) = Self::violation(&commit, branch_key.as_ref(), &cfg.hooks_cfg)?
+                {
+                    rejected.push(format!(
+                        "  {} {} ({reason})",
+                        commit.short_id(),
+                        commit.subject()
+                    ));
+                }
+            }
+        }
+
+        if rejected.is_empty() {
+            return Ok(());
+        }
+
+        Err(eyre!(
+            "Push rejected, {} commit(s) failing the commit message rules:\n{}\n  Reword with: git rebase -i, or skip with: git push --no-verify",
+            rejected.len(),
+            rejected.join("\n")
+        ))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn commit(message: &str) -> CommitInfo {
+        CommitInfo {
+            id: String::from("0123456789abcdef"),
+            message: String::from(message),
+            parent_count: 1,
+        }
+    }
+
+    fn key(key: &str) -> IssueKey {
+        IssueKey::try_from(String::from(key)).expect("Valid issue key")
+    }
+
+    #[test]
+    fn parse_ref_updates() {
+        let input =
+            "refs/heads/JB-1_x 1111 refs/heads/JB-1_x 0000\nrefs/tags/v1 2222 refs/tags/v1 0000\n";
+        let updates = PrePush::parse_updates(input);
+        assert_eq!(2, updates.len());
+        assert_eq!("refs/heads/JB-1_x", updates[0].local_ref);
+        assert!(is_zero(&updates[0].remote_sha));
+    }
+
+    #[test]
+    fn commit_violations() {
+        let cfg = GitHooksConfig::from(None);
+        let branch_key = key("JB-1");
+        let violation =
+            |msg: &str| PrePush::violation(&commit(msg), Some(&branch_key), &cfg).unwrap();
+
+        assert_eq!(None, violation("JB-1 Add the thing\n"));
+        assert_eq!(None, violation("fixup! JB-1 Add the thing\n"));
+        assert_eq!(
+            Some(String::from("missing issue key")),
+            violation("Add the thing\n\nFor JB-1\n")
+        );
+        assert_eq!(
+            Some(String::from("references JB-2, branch is JB-1")),
+            violation("JB-2 Add the thing\n")
+        );
+        // Same rules as the commit-msg hook
+        assert!(
+            violation("add the thing JB-1\n")
+                .is_some_and(|reason| reason.starts_with("message not conforming to regex"))
+        );
+        // Committed messages have no comments, the subject is not skipped
+        assert_eq!(
+            Some(String::from("missing issue key")),
+            violation("#12 Add the thing\n\nJB-1\n")
+        );
+    }
+
+    #[test]
+    fn branch_without_key_checks_commits() {
+        let cfg = GitHooksConfig::from(None);
+        let violation = |msg: &str| PrePush::violation(&commit(msg), None, &cfg).unwrap();
+        assert_eq!(None, violation("JB-1 Add the thing\n"));
+        assert_eq!(None, violation("JB-2 Fix the other thing\n"));
+        assert_eq!(
+            Some(String::from("missing issue key")),
+            violation("Add the thing\n")
+        );
+    }
+
+    #[test]
+    fn merge_commits_are_skipped() {
+        let cfg = GitHooksConfig::from(None);
+        let mut merge = commit("Merge branch 'main'\n");
+        merge.parent_count = 2;
+        assert_eq!(None, PrePush::violation(&merge, None, &cfg).unwrap());
+    }
+}
@@ -0,0 +1,296 @@
+use super::lib::Hook;
+use crate::{
+    client::JiraClient,
+    config::{Config, ReferenceStyle},
+    repo::{self, Repository},
+};
+use color_eyre::{
+    Result,
+    eyre::{WrapErr, eyre},
+};
+use jira::models::{Issue, IssueKey};
+use serde_json::Value;
+use std::{fmt::Display, path::PathBuf};
+
+/// Max number of acceptance criteria lines included as context
+const CONTEXT_MAX_LINES: usize = 5;
+/// Context lines are truncated to avoid wrapping in the editor
+const CONTEXT_MAX_WIDTH: usize = 72;
+/// Preparing the message should never noticeably delay the editor
+const CONTEXT_TIMEOUT_SECONDS: u64 = 3;
+
+#[derive(Debug)]
+pub struct PrepareCommitMsg {
+    commit_msg_file: PathBuf,
+    /// message, template, merge, squash or commit
+    source: Option<String>,
+    repo: Repository,
+}
+
+impl Display for PrepareCommitMsg {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", Self::hook_name())
+    }
+}
+
+impl PrepareCommitMsg {
+    /// Insert the issue key on the subject line and the context before git's own comments.
+    /// Buffers without a subject, suffix and trailer styles are left for the commit-msg hook
+    /// to add the key to once the subject is written.
+    fn prepare(
+        commit_msg: &str,
+        issue_key: &IssueKey,
+        style: ReferenceStyle,
+        context: &[String],
+        comment: char,
+    ) -> String {
+        let mut lines = commit_msg
+            .lines()
+            .map(String::from)
+            .collect::<Vec<String>>();
+        let key = issue_key.to_string();
+        let reference = match style {
+            ReferenceStyle::Prefix => Some(key.clone()),
+            ReferenceStyle::Bracket => Some(format!("[{key}]")),
+            ReferenceStyle::Suffix | ReferenceStyle::Trailer => None,
+        };
+
+        let subject = lines
+            .iter()
+            .position(|l| !l.starts_with(comment) && !l.trim().is_empty());
+        // Template with content, blank lines before it are dropped by git
+        if let (Some(i), Some(reference)) = (subject, reference)
+            && !lines[i].contains(&key)
+            && lines[..i].iter().all(|l| l.trim().is_empty())
+        {
+            lines[i] = format!("{reference} {}", lines[i])
+        }
+
+        if !context.is_empty() {
+            let comments_start = lines
+                .iter()
+                .position(|l| l.starts_with(comment))
+                .unwrap_or(lines.len());
+            let context_lines = context.iter().map(|l| {
+                let line = format!("{comment} {l}");
+                line.trim_end()
+                    .chars()
+                    .take(CONTEXT_MAX_WIDTH + 2)
+                    .collect()
+            });
+
+            // Keep the context apart from the message
+            let mut block = match comments_start.checked_sub(1).map(|i| lines[i].trim()) {
+                Some("") => vec![],
+                _ => vec![String::default()],
+            };
+            block.extend(context_lines);
+            lines.splice(comments_start..comments_start, block);
+        }
+
+        let mut msg = lines.join("\n");
+        msg.push('\n');
+        msg
+    }
+
+    async fn context(cfg: &Config, issue_key: &IssueKey) -> Result<Vec<String>> {
+        let mut cfg = cfg.to_owned();
+        cfg.jira_cfg.timeout = cfg.jira_cfg.timeout.min(CONTEXT_TIMEOUT_SECONDS);
+
+        let client = JiraClient::new(&cfg)?;
+        let issue = client.get_issue(issue_key, Some("names")).await?;
+
+        let mut context = vec![format!(
+            "{issue_key}: {}",
+            issue.fields.summary.clone().unwrap_or_default()
+        )];
+        if let Some(status) = &issue.fields.status {
+            context.push(format!("Status: {}", status.name));
+        }
+
+        let criteria = Self::acceptance_criteria(&issue);
+        if !criteria.is_empty() {
+            context.push(String::from("Acceptance criteria:"));
+            context.extend(criteria.into_iter().map(|l| format!("  {l}")));
+        }
+
+        Ok(context)
+    }
+
+    /// Prefer a custom field named acceptance criteria, fall back to a section of the description.
+    fn acceptance_criteria(issue: &Issue) -> Vec<String> {
+        let is_criteria = |name: &str| name.to_lowercase().contains("acceptance criteria");
+
+        let custom_field = issue.names.as_ref().and_then(|names| {
+            // Names are unordered, the first field by id is picked when several match
+            let mut ids = names
+                .iter()
+                .filter(|(_, name)| is_criteria(name))
+                .map(|(id, _)| id)
+                .collect::<Vec<_>>();
+            ids.sort();
+            ids.into_iter()
+                .find_map(|id| match issue.fields.customfields.get(id) {
+                    Some(Value::String(text)) if !text.trim().is_empty() => Some(text.clone()),
+                    _ => None,
+                })
+        });
+
+        let text = match custom_field {
+            Some(text) => text,
+            None => {
+                let description = issue.fields.description.clone().unwrap_or_default();
+                match description.lines().position(is_criteria) {
+                    Some(i) => description
+                        .lines()
+                        .skip(i + 1)
+                        .collect::<Vec<_>>()
+                        .join("\n"),
+                    None => String::default(),
+                }
+            }
+        };
+
+        text.lines()
+            .map(str::trim)
+            .filter(|l| !l.is_empty())
+            .take(CONTEXT_MAX_LINES)
+            .map(String::from)
+            .collect()
+    }
+}
+
+impl Hook for PrepareCommitMsg {
+    fn hook_name() -> String {
+        String::from("prepare-commit-msg")
+    }
+
+    fn new(args: &[String]) -> Result<PrepareCommitMsg> {
+        let commit_msg_file = PathBuf::from(
+            args.first()
+                .ok_or(eyre!("Expected commit_msg_file as first argument"))?,
+        );
+        let repo = repo::Repository::open().wrap_err("Failed to open repository")?;
+
+        Ok(PrepareCommitMsg {
+            commit_msg_file,
+            // Hook managers pass missing arguments as empty strings
+            source: args.get(1).filter(|source| !source.is_empty()).cloned(),
+            repo,
+        })
+    }
+
+    async fn exec(self, cfg: &Config) -> Result<()> {
+        // Compat: messages from -m/-F, merges and squashes are already complete
+        if matches!(
+            self.source.as_deref(),
+            Some("message") | Some("merge") | Some("squash")
+        ) {
+            return Ok(());
+        }
+
+        // Missing keys are handled by the commit-msg hook
+        let Ok(issue_key) = IssueKey::try_from(self.repo.get_branch_name()?) else {
+            return Ok(());
+        };
+
+        let commit_msg =
+            std::fs::read_to_string(&self.commit_msg_file).wrap_err("Failed to read commit_msg")?;
+        let context = match cfg.hooks_cfg.prepare_commit_msg_issue_context {
+            true => Self::context(cfg, &issue_key).await.unwrap_or_default(),
+            false => vec![],
+        };
+
+        let msg = Self::prepare(
+            &commit_msg,
+            &issue_key,
+            cfg.hooks_cfg.reference_style,
+            &context,
+            self.repo.get_comment_char(),
+        );
+        std::fs::write(&self.commit_msg_file, msg).wrap_err("Failed to write new commit_msg")
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn key() -> IssueKey {
+        IssueKey::try_from(String::from("JB-12")).expect("Valid issue key")
+    }
+
+    #[test]
+    fn prepare_empty_editor_buffer() {
+        let msg = "\n# Please enter the commit message for your changes.\n";
+        let prepared = PrepareCommitMsg::prepare(msg, &key(), ReferenceStyle::Prefix, &[], '#');
+        assert_eq!(msg, prepared);
+    }
+
+    #[test]
+    fn prepare_template_without_key() {
+        let msg = "Subject from template\n\nBody\n# comment\n";
+        let prepared = PrepareCommitMsg::prepare(msg, &key(), ReferenceStyle::Prefix, &[], '#');
+        assert_eq!("JB-12 Subject from template\n\nBody\n# comment\n", prepared);
+    }
+
+    #[test]
+    fn prepare_template_with_leading_blank_lines() {
+        let msg = "\n\nSubject from template\n# comment\n";
+        let prepared = PrepareCommitMsg::prepare(msg, &key(), ReferenceStyle::Prefix, &[], '#');
+        assert_eq!("\n\nJB-12 Subject from template\n# comment\n", prepared);
+    }
+
+    #[test]
+    fn acceptance_criteria_field_by_id() {
+        let issue: Issue = serde_json::from_value(serde_json::json!({
+            "key": "JB-12",
+            "self": "https://jira.example.com/rest/api/2/issue/1",
+            "fields": {
+                "customfield_10020": "Second field",
+                "customfield_10010": "\n  First field\n\nMore\n",
+                "description": "Acceptance criteria:\nFrom description",
+            },
+            "names": {
+                "customfield_10020": "Acceptance Criteria (legacy)",
+                "customfield_10010": "Acceptance criteria",
+            },
+        }))
+        .unwrap();
+        assert_eq!(
+            vec!["First field", "More"],
+            PrepareCommitMsg::acceptance_criteria(&issue)
+        );
+    }
+
+    #[test]
+    fn prepare_amend_with_existing_key() {
+        let msg = "JB-12 Existing subject\n\n# comment\n";
+        let prepared = PrepareCommitMsg::prepare(msg, &key(), ReferenceStyle::Prefix, &[], '#');
+        assert_eq!(msg, prepared);
+    }
+
+    #[test]
+    fn prepare_context_before_git_comments() {
+        let msg = "\n# Please enter the commit message for your changes.\n";
+        let context = vec![
+            String::from("JB-12: Summary"),
+            String::from("Status: To Do"),
+        ];
+        let prepared =
+            PrepareCommitMsg::prepare(msg, &key(), ReferenceStyle::Prefix, &context, '#');
+        assert_eq!(
+            "\n# JB-12: Summary\n# Status: To Do\n# Please enter the commit message for your changes.\n",
+            prepared
+        );
+    }
+
+    #[test]
+    fn prepare_reference_styles() {
+        let msg = "\n# comment\n";
+        let bracket = PrepareCommitMsg::prepare(msg, &key(), ReferenceStyle::Bracket, &[], '#');
+        assert_eq!(msg, bracket);
+        let trailer = PrepareCommitMsg::prepare(msg, &key(), ReferenceStyle::Trailer, &[], '#');
+        assert_eq!(msg, trailer);
+    }
+}
@@ -0,0 +1,112 @@
+use super::{branch_policy, lib::Hook, pre_push::is_zero};
+use crate::{config::Config, repo::Repository};
+use color_eyre::eyre::{Result, WrapErr, eyre};
+use std::fmt::Display;
+
+#[derive(Debug)]
+pub struct ReferenceTransaction {
+    /// prepared, committed or aborted
+    state: String,
+    /// Lines of `<old-value> <new-value> <ref-name>`
+    pub(super) input: String,
+}
+
+impl Display for ReferenceTransaction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", Self::hook_name())
+    }
+}
+
+impl ReferenceTransaction {
+    pub(super) fn is_prepared(&self) -> bool {
+        self.state == "prepared"
+    }
+
+    /// Names of the local branches created by the transaction.
+    /// Forced updates also pass a zero old value, `exists` tells them apart from new refs
+    fn created_branches(input: &str, exists: impl Fn(&str) -> bool) -> Vec<&str> {
+        input
+            .lines()
+            .filter_map(
+                |line| match line.split_whitespace().collect::<Vec<_>>()[..] {
+                    [old, new, reference]
+                        if is_zero(old) && !is_zero(new) && !exists(reference) =>
+                    {
+                        reference.strip_prefix("refs/heads/")
+                    }
+                    _ => None,
+                },
+            )
+            .collect()
+    }
+}
+
+impl Hook for ReferenceTransaction {
+    fn hook_name() -> String {
+        String::from("reference-transaction")
+    }
+
+    fn new(args: &[String]) -> Result<ReferenceTransaction> {
+        let state = args
+            .first()
+            .cloned()
+            .ok_or(eyre!("Expected transaction state as first argument"))?;
+        // Other states are skipped, git ignores hooks not reading their input
+        let input = match state.as_str() {
+            "prepared" => {
+                std::io::read_to_string(std::io::stdin()).wrap_err("Failed to read stdin")?
+            }
+            _ => String::default(),
+        };
+
+        Ok(ReferenceTransaction { state, input })
+    }
+
+    /// Runs for every ref update, only failing in the prepared state aborts the transaction
+    async fn exec(self, cfg: &Config) -> Result<()> {
+        let policy = &cfg.hooks_cfg.branch_policy;
+        if !self.is_prepared() || !policy.enabled {
+            return Ok(());
+        }
+
+        // Refs are not updated yet in the prepared state
+        let repo = Repository::open()?;
+        for branch in Self::created_branches(&self.input, |r| repo.rev_parse(r).is_ok()) {
+            let violations = branch_policy::check(branch, policy)?;
+            if !violations.is_empty() {
+                return Err(branch_policy::rejection(branch, &violations));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn only_created_branches() {
+        let zero = "0".repeat(40);
+        let sha = "1".repeat(40);
+        let input = format!(
+            "{zero} {sha} refs/heads/JB-1_new\n{sha} {sha} refs/heads/main\n{zero} {sha} refs/tags/v1\n{sha} {zero} refs/heads/old\n"
+        );
+        assert_eq!(
+            vec!["JB-1_new"],
+            ReferenceTransaction::created_branches(&input, |_| false)
+        );
+    }
+
+    #[test]
+    fn forced_update_of_existing_branch() {
+        let zero = "0".repeat(40);
+        let sha = "1".repeat(40);
+        // git checkout -B main, git branch -f main
+        let input = format!("{zero} {sha} refs/heads/main\n{zero} {sha} refs/heads/JB-1_new\n");
+        assert_eq!(
+            vec!["JB-1_new"],
+            ReferenceTransaction::created_branches(&input, |r| r == "refs/heads/main")
+        );
+    }
+}
@@ -0,0 +1,142 @@
+use crate::repo::Repository;
+use std::{
+    env::{self, current_exe},
+    fmt::Display,
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// Identifies hook scripts written by `jig hook install --shim`
+pub const SHIM_MARKER: &str = "# Installed by jig hook install --shim";
+/// Identifies scripts written by `jig hook install --global` for hooks jig does not run itself
+pub const FORWARD_MARKER: &str = "# Forwarded by jig hook install --global";
+/// Global git config key holding core.hooksPath from before `jig hook install --global`
+pub const HOOKS_PATH_BACKUP: &str = "jig.hooksPathBackup";
+
+/// What is found at the location of a hook
+#[derive(Debug, PartialEq, Eq)]
+pub enum HookState {
+    Missing,
+    /// Symlink to the running jig executable
+    Jig,
+    /// Script running jig from PATH
+    Shim,
+    /// Script running the hooks of the repository in place of a global jig hook
+    Forwarder,
+    /// Symlink to another executable, e.g. a previous jig install
+    OtherBinary(PathBuf),
+    /// Symlink to a file that does not exist
+    Broken(PathBuf),
+    /// Hook not managed by jig, e.g. a script of a hook manager
+    Foreign,
+}
+
+impl HookState {
+    /// Installs that fail or run an unexpected jig
+    pub fn is_broken(&self) -> bool {
+        match self {
+            HookState::Broken(_) | HookState::OtherBinary(_) => true,
+            HookState::Shim => !jig_in_path(),
+            _ => false,
+        }
+    }
+
+    pub fn of(hook: &Path) -> HookState {
+        let Ok(metadata) = fs::symlink_metadata(hook) else {
+            return HookState::Missing;
+        };
+        if !metadata.file_type().is_symlink() {
+            return match fs::read_to_string(hook) {
+                Ok(script) if script.contains(SHIM_MARKER) => HookState::Shim,
+                Ok(script) if script.contains(FORWARD_MARKER) => HookState::Forwarder,
+                _ => HookState::Foreign,
+            };
+        }
+
+        let canonical = |path: PathBuf| fs::canonicalize(path).ok();
+        let target = fs::read_link(hook)
+            .map(|target| hook.parent().unwrap_or(Path::new("")).join(target))
+            .unwrap_or_default();
+        match (
+            canonical(target.clone()),
+            current_exe().ok().and_then(canonical),
+        ) {
+            (Some(target), Some(exe)) if target == exe => HookState::Jig,
+            (Some(target), _) => HookState::OtherBinary(target),
+            (None, _) => HookState::Broken(target),
+        }
+    }
+}
+
+impl Display for HookState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            HookState::Missing => write!(f, "not installed"),
+            HookState::Jig => write!(f, "installed"),
+            HookState::Shim if !jig_in_path() => write!(f, "shim, but jig is not found in PATH"),
+            HookState::Shim => write!(f, "installed (shim)"),
+            HookState::Forwarder => write!(f, "forwarded to the repository hooks"),
+            HookState::OtherBinary(target) => {
+                write!(f, "points to another binary: {}", target.display())
+            }
+            HookState::Broken(target) => write!(f, "broken symlink to {}", target.display()),
+            HookState::Foreign => write!(f, "not managed by jig"),
+        }
+    }
+}
+
+/// Hooks git ignores while the global jig hooks are in use, run by jig instead.
+/// Those of the core.hooksPath replaced by the install, and those in .git/hooks of `repo`.
+pub fn forwarded_hooks(repo: &Repository, name: &str) -> Vec<PathBuf> {
+    let replaced = Repository::get_global_config(HOOKS_PATH_BACKUP)
+        .ok()
+        .flatten()
+        .and_then(|path| Repository::expand_home(&path).ok());
+    let local = Some(repo.default_hooks_path());
+
+    let mut hooks = [replaced, local]
+        .into_iter()
+        .flatten()
+        .map(|hooks_path| hooks_path.join(name))
+        // Hooks installed by jig would run twice
+        .filter(|hook| {
+            matches!(
+                HookState::of(hook),
+                HookState::Foreign | HookState::OtherBinary(_)
+            ) && is_executable(hook)
+        })
+        .collect::<Vec<_>>();
+    hooks.dedup();
+    hooks
+}
+
+/// Git skips hooks that are not executable
+fn is_executable(path: &Path) -> bool {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::metadata(path).is_ok_and(|m| m.permissions().mode() & 0o111 != 0)
+    }
+    #[cfg(not(unix))]
+    {
+        path.is_file()
+    }
+}
+
+pub fn jig_in_path() -> bool {
+    let Some(path) = env::var_os("PATH") else {
+        return false;
+    };
+    env::split_paths(&path).any(|dir| dir.join("jig").is_file() || dir.join("jig.exe").is_file())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn missing_hook_state() {
+        let hook = std::env::temp_dir().join("jig-missing-hook/commit-msg");
+        assert_eq!(HookState::Missing, HookState::of(&hook));
+    }
+}
@@ -1,15 +1,25 @@
-use crate::config::Config;
+use crate::{
+    cache::{self, Cached},
+    client::JiraClient,
+    config::Config,
+};
 use chrono::Utc;
-use color_eyre::eyre::{Result, WrapErr, eyre};
-use jira::{
-    JiraAPIClient,
-    models::{Issue, IssueKey},
+use color_eyre::{
+    eyre::{Report, Result, WrapErr, eyre},
+    owo_colors::OwoColorize,
 };
+use jira::models::{Issue, IssueKey};
+use std::collections::BTreeMap;
+
+const ISSUE_QUERY_CACHE: &str = "issue_query";
+
+/// Last successful results per issue_query
+type IssueQueryCache = BTreeMap<String, Cached<Vec<Issue>>>;
 
 // Might be useful one day
 #[allow(dead_code)]
 pub async fn issue_key_from_branch_or_prompt(
-    client: &JiraAPIClient,
+    client: &JiraClient,
     cfg: &Config,
     head_name: String,
 ) -> Result<IssueKey> {
@@ -23,7 +33,7 @@ pub async fn issue_key_from_branch_or_prompt(
 }
 
 pub async fn issue_from_branch_or_prompt(
-    client: &JiraAPIClient,
+    client: &JiraClient,
     cfg: &Config,
     head_name: String,
 ) -> Result<Issue> {
@@ -49,6 +59,77 @@ pub fn prompt_user_with_issue_select(issues: Vec<Issue>) -> Result<Issue> {
         .wrap_err("No issue selected")
 }
 
+/// Jira could not be reached in time, as opposed to answering with an error
+fn is_unreachable(err: &Report) -> bool {
+    err.chain()
+        .filter_map(|e| e.downcast_ref::<reqwest::Error>())
+        .any(|e| e.is_connect() || e.is_timeout())
+}
+
+/// Prompt with the issue_query results, falling back to the last cached results
+/// or a manually typed key when Jira is unreachable.
+pub async fn prompt_user_with_issue_key_select(cfg: &Config) -> Result<IssueKey> {
+    use inquire::{Select, Text};
+
+    let query = cfg.issue_query.as_str();
+    let mut cache: IssueQueryCache = cache::read(ISSUE_QUERY_CACHE).unwrap_or_default();
+    // Client setup failures, e.g. an unreadable certificate, also leave Jira unreachable
+    let err = match JiraClient::new(cfg) {
+        Ok(client) => match query_issues_empty_err(&client, query).await {
+            Ok(issues) => {
+                cache.insert(query.to_string(), Cached::new(issues.clone()));
+                // Failing to cache only disables the offline fallback
+                let _ = cache::write(ISSUE_QUERY_CACHE, &cache);
+                return Ok(prompt_user_with_issue_select(issues)?.key);
+            }
+            Err(e) if !is_unreachable(&e) => return Err(e),
+            Err(e) => e,
+        },
+        Err(e) => e,
+    };
+
+    eprintln!(
+        "{}",
+        format!("{err:#}, falling back to cached issues").bright_yellow()
+    );
+    let manual_entry = String::from("Enter issue key manually");
+    let (message, mut options) = match cache.remove(query) {
+        Some(cached) if !cached.value.is_empty() => (
+            format!(
+                "Jira issue (stale, cached {} ago):",
+                format_age(cached.age_seconds())
+            ),
+            cached.value,
+        ),
+        _ => (String::default(), vec![]),
+    };
+
+    if !options.is_empty() {
+        let mut labels = options.iter().map(Issue::to_string).collect::<Vec<_>>();
+        labels.push(manual_entry);
+        let selected = Select::new(&message, labels)
+            .raw_prompt()
+            .wrap_err("No issue selected")?;
+        if selected.index < options.len() {
+            return Ok(options.swap_remove(selected.index).key);
+        }
+    }
+
+    let key = Text::new("Issue key:")
+        .prompt()
+        .wrap_err("No issue key entered")?;
+    IssueKey::try_from(key.trim().to_string()).map_err(|_| eyre!("Invalid issue key: '{key}'"))
+}
+
+fn format_age(seconds: i64) -> String {
+    match seconds {
+        ..60 => format!("{seconds}s"),
+        60..3600 => format!("{}m", seconds / 60),
+        3600..86400 => format!("{}h", seconds / 3600),
+        _ => format!("{}d", seconds / 86400),
+    }
+}
+
 /// Almost rfc3339 AKA Jira compatible
 pub fn now() -> String {
     // Jira sucks and can't parse correct rfc3339 due to the ':' in tz.. https://jira.atlassian.com/browse/JRASERVER-61378
@@ -56,14 +137,14 @@ pub fn now() -> String {
     Utc::now().format("%FT%X%.3f%z").to_string()
 }
 
-pub async fn query_issue_details(client: &JiraAPIClient, issue_key: IssueKey) -> Result<Issue> {
+pub async fn query_issue_details(client: &JiraClient, issue_key: IssueKey) -> Result<Issue> {
     client
         .get_issue(&issue_key, None)
         .await
         .wrap_err("Fetching issue details failed")
 }
 
-pub async fn query_issues_empty_err(client: &JiraAPIClient, query: &str) -> Result<Vec<Issue>> {
+pub async fn query_issues_empty_err(client: &JiraClient, query: &str) -> Result<Vec<Issue>> {
     match client
         .query_issues(query, Some(vec!["summary".to_string()]), None)
         .await
@@ -76,3 +157,29 @@ pub async fn query_issues_empty_err(client: &JiraAPIClient, query: &str) -> Resu
         Err(e) => Err(eyre!(e)),
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use jira::JiraClientError;
+
+    #[test]
+    fn fallback_only_when_unreachable() {
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        // Nothing listens on port 1
+        let refused = runtime
+            .block_on(reqwest::Client::new().get("http://127.0.0.1:1").send())
+            .unwrap_err();
+        let query_failed = |e: JiraClientError| Report::new(e).wrap_err("Issue query failed");
+
+        assert!(is_unreachable(&query_failed(JiraClientError::HttpError(
+            refused
+        ))));
+        assert!(!is_unreachable(&query_failed(
+            JiraClientError::JiraQueryAuthenticationError()
+        )));
+        assert!(!is_unreachable(&eyre!(
+            "No issues found using given issue_query"
+        )));
+    }
+}
@@ -1,3 +1,5 @@
+mod cache;
+mod client;
 mod commands;
 mod config;
 mod hooks;
@@ -6,10 +8,9 @@ mod repo;
 
 use clap::{CommandFactory, Parser, Subcommand};
 use color_eyre::eyre::{Result, WrapErr};
-use color_eyre::owo_colors::OwoColorize;
 use commands::{shared::ExecCommand, *};
 use config::Config;
-use hooks::{Hook, is_git_hook};
+use hooks::is_git_hook;
 use inquire::InquireError;
 
 #[derive(Parser)]
@@ -27,6 +28,8 @@ enum Commands {
     /// Create and checkout branch using issue key with(out) summary as branch name
     #[command(visible_alias = "b")]
     Branch(Branch),
+    /// Generate release notes from the issues referenced in a commit range
+    Changelog(Changelog),
     /// Create comment on a Jira Issue
     #[command(visible_alias = "c")]
     Comment(Comment),
@@ -35,7 +38,11 @@ enum Commands {
     Completion(Completion),
     /// List config file locations
     Configs(PrintConfigs),
-    /// Install git commit-msg hook
+    /// Diagnose config, connectivity and hook installation
+    Doctor(Doctor),
+    /// Add missing issue keys to the commit messages of a branch
+    FixCommits(FixCommits),
+    /// Install, uninstall and inspect git hooks
     Hook(Hooks),
     /// Initialise config file(s)
     Init(InitConfig),
@@ -51,9 +58,13 @@ enum Commands {
     /// Interactively send JQL queries to Jira when tab is pressed
     #[command(visible_alias = "search")]
     Query(Query),
+    /// Add a fix version to and transition the issues referenced in a commit range
+    Release(Release),
     /// Download and install latest version
     #[command(visible_alias = "update")]
     Upgrade(Upgrade),
+    /// Verify the commit messages of a range against the hook rules, for CI
+    Verify(Verify),
 }
 
 impl Commands {
@@ -63,16 +74,21 @@ impl Commands {
         match args.command {
             Commands::Assign(assign) => assign.exec(&cfg?).await,
             Commands::Branch(branch) => branch.exec(&cfg?).await,
+            Commands::Changelog(changelog) => changelog.exec(&cfg?).await,
             Commands::Comment(comment) => comment.exec(&cfg?).await,
             Commands::Completion(completion) => completion.exec(&mut Cli::command()),
             Commands::Configs(print_config) => print_config.exec(&cfg?).await,
-            Commands::Hook(hooks) => hooks.install(),
-            Commands::Init(init) => init.init(),
+            Commands::Doctor(doctor) => doctor.diagnose(cfg).await,
+            Commands::FixCommits(fix_commits) => fix_commits.exec(&cfg?).await,
+            Commands::Hook(hooks) => hooks.exec(cfg).await,
+            Commands::Init(init) => init.init().await,
             Commands::Worklog(worklog) => worklog.exec(&cfg?).await,
             Commands::Transition(transition) => transition.exec(&cfg?).await,
             Commands::Open(open) => open.exec(&cfg?).await,
             Commands::Query(query) => query.exec(&cfg?).await,
-            Commands::Upgrade(upgrade) => upgrade.upgrade().await,
+            Commands::Release(release) => release.exec(&cfg?).await,
+            Commands::Upgrade(upgrade) => upgrade.upgrade(cfg.ok()).await,
+            Commands::Verify(verify) => verify.exec(&cfg?).await,
         }
     }
 }
@@ -80,24 +96,12 @@ impl Commands {
 #[tokio::main]
 async fn main() -> Result<()> {
     color_eyre::install()?;
-    let cfg = config::Config::load().wrap_err("Failed to load config");
+    let load_config = || config::Config::load().wrap_err("Failed to load config");
 
-    if let Some(githook) = is_git_hook()? {
-        match githook.exec(&cfg?).await {
-            Ok(_) => (),
-            Err(e) => {
-                match e.root_cause().downcast_ref::<InquireError>() {
-                    Some(InquireError::OperationInterrupted)
-                    | Some(InquireError::OperationCanceled) => std::process::exit(1),
-                    _ => {
-                        // Add better comments from old hook
-                        eprintln!("{}", format!("Jig githook error:\n   {e}").bright_red());
-                        std::process::exit(1);
-                    }
-                }
-            }
-        }
+    if let Some((githook, args)) = is_git_hook()? {
+        hooks::run(githook, load_config, &args).await;
     } else {
+        let cfg = load_config();
         let res = Commands::exec(cfg).await;
         match res {
             Ok(msg) => println!("{msg}"),
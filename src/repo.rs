@@ -4,29 +4,60 @@ use color_eyre::eyre::{Result, WrapErr, eyre};
 use gix::bstr::ByteSlice;
 use gix::{Repository as Gix_Repository, ThreadSafeRepository};
 use jira::models::{Issue, IssueKey};
+use std::env::var;
 use std::str::FromStr;
-use std::{path::PathBuf, process::Command};
+use std::{
+    io::Write,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+};
 
 #[derive(Debug, Clone)]
 pub struct Repository {
     repo: Gix_Repository,
 }
 
+#[derive(Debug, Clone)]
+pub struct CommitInfo {
+    pub id: String,
+    pub message: String,
+    pub parent_count: usize,
+}
+
+impl CommitInfo {
+    pub fn short_id(&self) -> &str {
+        &self.id[..self.id.len().min(8)]
+    }
+
+    pub fn subject(&self) -> &str {
+        self.message.lines().next().unwrap_or_default()
+    }
+}
+
 impl Repository {
     pub fn open() -> Result<Self> {
         let (path, _is_repo) = find_workspace();
+        Self::open_at(&path)
+    }
+
+    pub fn open_at(path: &Path) -> Result<Self> {
         Ok(Repository {
             repo: ThreadSafeRepository::open(path)?.to_thread_local(),
         })
     }
 
-    pub fn get_branch_name(&self) -> Result<String> {
-        let head_ref = self.repo.head_ref()?;
-        let head_commit = self.repo.head_commit()?;
+    /// git command running in this repository rather than the current directory
+    fn git(&self) -> Command {
+        let mut git = Command::new("git");
+        git.current_dir(self.repo.workdir().unwrap_or(self.repo.git_dir()));
+        git
+    }
 
-        match head_ref {
-            Some(reference) => Ok(reference.name().shorten().to_string()),
-            None => Ok(head_commit.id.to_hex_with_len(8).to_string()),
+    pub fn get_branch_name(&self) -> Result<String> {
+        // head_name over head_ref, as it also resolves unborn branches
+        match self.repo.head_name()? {
+            Some(name) => Ok(name.shorten().to_string()),
+            None => Ok(self.repo.head_commit()?.id.to_hex_with_len(8).to_string()),
         }
     }
 
@@ -143,31 +174,320 @@ impl Repository {
         }
     }
 
+    /// git reading and writing the global config, tests use a file of their own
+    fn global_git() -> Command {
+        let git = Command::new("git");
+        #[cfg(test)]
+        let git = testing::with_global_config(git);
+        git
+    }
+
+    /// Value of `key` in the global git config
+    pub fn get_global_config(key: &str) -> Result<Option<String>> {
+        let output = Self::global_git()
+            .args(["config", "--global", "--get", key])
+            .output()
+            .wrap_err("Failed to run git config")?;
+
+        // Exit code 1 means the key is not set
+        match output.status.code() {
+            Some(0) => Ok(Some(
+                String::from_utf8_lossy(&output.stdout).trim().to_string(),
+            )),
+            Some(1) => Ok(None),
+            _ => Err(eyre!(
+                "Failed to read {key} from the global git config: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            )),
+        }
+    }
+
+    /// Set `key` in the global git config, unsetting it for None
+    pub fn set_global_config(key: &str, value: Option<&str>) -> Result<()> {
+        let args = match value {
+            Some(value) => vec!["config", "--global", key, value],
+            None => vec!["config", "--global", "--unset", key],
+        };
+        let output = Self::global_git()
+            .args(args)
+            .output()
+            .wrap_err("Failed to run git config")?;
+
+        // Exit code 5 means the key to unset was not set
+        match output.status.code() {
+            Some(0) => Ok(()),
+            Some(5) if value.is_none() => Ok(()),
+            _ => Err(eyre!(
+                "Failed to write {key} to the global git config: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            )),
+        }
+    }
+
+    /// True while git is in the middle of an operation recorded by `state_file`, e.g. MERGE_HEAD
+    pub fn in_progress(&self, state_file: &str) -> bool {
+        self.repo.git_dir().join(state_file).exists()
+    }
+
+    /// Lines starting with core.commentChar are stripped from commit messages by git.
+    /// Defaults to '#', 'auto' is treated as the default.
+    pub fn get_comment_char(&self) -> char {
+        let cfg = self.repo.config_snapshot();
+        match cfg.string("core.commentChar") {
+            Some(c) if c.as_bytes() != b"auto" => c.to_str_lossy().chars().next().unwrap_or('#'),
+            _ => '#',
+        }
+    }
+
+    /// Commits reachable from `tip` but not from any of `hidden`, newest first.
+    /// Hidden revisions unknown to the local repository are ignored.
+    pub fn get_commits(&self, tip: &str, hidden: &[String]) -> Result<Vec<CommitInfo>> {
+        let tip = self
+            .repo
+            .rev_parse_single(tip)
+            .wrap_err(format!("Unknown revision: {tip}"))?
+            .detach();
+        let hidden = hidden
+            .iter()
+            .filter_map(|rev| self.repo.rev_parse_single(rev.as_str()).ok())
+            .map(|id| id.detach())
+            .collect::<Vec<_>>();
+
+        self.repo
+            .rev_walk([tip])
+            .with_hidden(hidden)
+            .all()?
+            .map(|info| {
+                let info = info?;
+                let commit = info.object()?;
+                Ok(CommitInfo {
+                    id: info.id.to_string(),
+                    message: commit.message_raw_sloppy().to_str_lossy().to_string(),
+                    parent_count: info.parent_ids().count(),
+                })
+            })
+            .collect()
+    }
+
+    /// Split `base..tip` into its ends, a single revision is the base
+    pub fn split_range(range: &str) -> Result<(&str, &str)> {
+        if range.contains("...") {
+            return Err(eyre!(
+                "Symmetric difference ranges are not supported: {range}"
+            ))
+            .with_suggestion(|| "Use a two-dot range: <base>..<tip>");
+        }
+        Ok(match range.split_once("..") {
+            Some((base, "")) => (base, "HEAD"),
+            Some((base, tip)) => (base, tip),
+            None => (range, "HEAD"),
+        })
+    }
+
+    /// Full commit id of `rev`
+    pub fn rev_parse(&self, rev: &str) -> Result<String> {
+        Ok(self
+            .repo
+            .rev_parse_single(rev)
+            .wrap_err(format!("Unknown revision: {rev}"))?
+            .to_string())
+    }
+
+    /// Commit ids of the remote-tracking branches of `remote`
+    pub fn get_remote_tips(&self, remote: &str) -> Result<Vec<String>> {
+        Ok(self
+            .repo
+            .references()?
+            .prefixed(format!("refs/remotes/{remote}/").as_str())?
+            .filter_map(|r| r.ok()?.try_id().map(|id| id.to_string()))
+            .collect())
+    }
+
+    /// Commit ids of the remote-tracking branches of all remotes
+    pub fn get_all_remote_tips(&self) -> Result<Vec<String>> {
+        Ok(self
+            .repo
+            .references()?
+            .remote_branches()?
+            .filter_map(|r| r.ok()?.try_id().map(|id| id.to_string()))
+            .collect())
+    }
+
+    /// Copy of commit `id` on top of `parent` with a new message, keeping its tree and author
+    pub fn reword_commit(&self, id: &str, parent: Option<&str>, message: &str) -> Result<String> {
+        let output = self
+            .git()
+            .args(["show", "-s", "--date=raw", "--format=%T%n%an%n%ae%n%ad", id])
+            .output()
+            .wrap_err("Failed to run git show")?;
+        if !output.status.success() {
+            return Err(eyre!(
+                "Failed to read commit {id}: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            ));
+        }
+        let info = String::from_utf8_lossy(&output.stdout);
+        let [tree, name, email, date] = info.lines().collect::<Vec<_>>()[..] else {
+            return Err(eyre!("Unexpected output of git show for commit {id}"));
+        };
+
+        let mut args = vec!["commit-tree", tree, "-F", "-"];
+        if let Some(parent) = parent {
+            args.extend(["-p", parent]);
+        }
+        let mut child = self
+            .git()
+            .args(args)
+            .env("GIT_AUTHOR_NAME", name)
+            .env("GIT_AUTHOR_EMAIL", email)
+            .env("GIT_AUTHOR_DATE", date)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .wrap_err("Failed to run git commit-tree")?;
+        child
+            .stdin
+            .take()
+            .expect("Stdin is piped")
+            .write_all(message.as_bytes())
+            .wrap_err("Failed to write commit message to git commit-tree")?;
+        let output = child
+            .wait_with_output()
+            .wrap_err("Failed to run git commit-tree")?;
+        if !output.status.success() {
+            return Err(eyre!(
+                "Failed to rewrite commit {id}: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            ));
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+    }
+
+    /// Point `branch` at `new`, failing if it moved away from `old` in the meantime
+    pub fn update_branch(&self, branch: &str, new: &str, old: &str, reason: &str) -> Result<()> {
+        let output = self
+            .git()
+            .args([
+                "update-ref",
+                "-m",
+                reason,
+                &format!("refs/heads/{branch}"),
+                new,
+                old,
+            ])
+            .output()
+            .wrap_err("Failed to run git update-ref")?;
+        match output.status.success() {
+            true => Ok(()),
+            false => Err(eyre!(
+                "Failed to update branch {branch}: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            )),
+        }
+    }
+
     pub fn get_hooks_path(&self) -> Result<PathBuf> {
         let cfg = self.repo.config_snapshot();
         if let Some(path) = cfg.string("core.hooksPath")
             && !path.trim().is_empty()
         {
-            return Ok(PathBuf::from_str(path.to_str()?)?);
+            let path = Self::expand_home(path.to_str()?)?;
+            // Relative paths are relative to the directory hooks run in, the work tree
+            return Ok(match self.repo.workdir() {
+                Some(workdir) if path.is_relative() => workdir.join(path),
+                _ => path,
+            });
         }
 
-        Self::default_hooks_path()
+        Ok(self.default_hooks_path())
     }
 
-    fn default_hooks_path() -> Result<PathBuf> {
-        let (workspace_path, is_repo) = find_workspace();
-
-        if is_repo {
-            let mut hooks_path = workspace_path;
-            hooks_path.push(".git");
-            hooks_path.push("hooks");
-            Ok(hooks_path)
-        } else {
-            Err(eyre!("Unable to decide on install location")).wrap_err(
-                "Current directory is not a Git repository and global core.hooksPath is undefined",
-            ).with_suggestion(|| "Try configuring a global core.hooksPath: git config --global set core.hooksPath <directory>")
+    /// Path from the git config, where a leading `~` is the home directory
+    pub fn expand_home(path: &str) -> Result<PathBuf> {
+        match path.strip_prefix("~") {
+            Some(rest) => Ok(PathBuf::from(format!("{}{rest}", var("HOME")?))),
+            None => Ok(PathBuf::from_str(path)?),
         }
     }
+
+    /// Hooks directory without core.hooksPath, shared by linked worktrees
+    pub fn default_hooks_path(&self) -> PathBuf {
+        self.repo.common_dir().join("hooks")
+    }
+}
+
+/// Temporary repositories for tests running git
+#[cfg(test)]
+pub(crate) mod testing {
+    use std::{
+        cell::RefCell,
+        fs,
+        path::{Path, PathBuf},
+        process::Command,
+    };
+
+    thread_local! {
+        /// Global git config file of the test running on this thread, instead of the user's
+        pub static GLOBAL_CONFIG: RefCell<Option<PathBuf>> = const { RefCell::new(None) };
+    }
+
+    /// Point `git` at the global config of the test, leaving the environment of other tests as is
+    pub fn with_global_config(mut git: Command) -> Command {
+        GLOBAL_CONFIG.with_borrow(|file| {
+            if let Some(file) = file {
+                git.env("GIT_CONFIG_GLOBAL", file);
+            }
+        });
+        git
+    }
+
+    /// Run git in `dir` without the user's config, returns stdout
+    pub fn git(dir: &Path, args: &[&str]) -> String {
+        let output = Command::new("git")
+            .args(args)
+            .current_dir(dir)
+            .env("GIT_CONFIG_GLOBAL", "/dev/null")
+            .env("GIT_CONFIG_NOSYSTEM", "1")
+            .output()
+            .expect("git is installed");
+        assert!(
+            output.status.success(),
+            "git {args:?} failed: {}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+        String::from_utf8_lossy(&output.stdout).trim().to_string()
+    }
+
+    /// Empty repository with an identity, in a directory unique to the test
+    pub fn init_repo(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("jig-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).expect("Temp dir is writable");
+        git(&dir, &["init", "-q", "-b", "main"]);
+        git(&dir, &["config", "user.name", "Committer"]);
+        git(&dir, &["config", "user.email", "committer@example.com"]);
+        dir
+    }
+
+    /// Commit a new file named after the message, by `author`
+    pub fn commit(dir: &Path, message: &str, author: &str) -> String {
+        fs::write(dir.join(message.replace(' ', "_")), message).expect("Temp dir is writable");
+        git(dir, &["add", "-A"]);
+        git(
+            dir,
+            &[
+                "commit",
+                "-q",
+                "-m",
+                message,
+                "--author",
+                author,
+                "--no-verify",
+            ],
+        );
+        git(dir, &["rev-parse", "HEAD"])
+    }
 }
 
 #[cfg(test)]
@@ -193,6 +513,52 @@ mod test {
         }
     }
 
+    #[test]
+    fn split_ranges() {
+        let split = |range| Repository::split_range(range).unwrap();
+        assert_eq!(("main", "HEAD"), split("main"));
+        assert_eq!(("main", "HEAD"), split("main.."));
+        assert_eq!(("main", "JB-1_x"), split("main..JB-1_x"));
+        assert_eq!(("origin/main", "HEAD"), split("origin/main..HEAD"));
+        assert!(Repository::split_range("main...JB-1_x").is_err());
+    }
+
+    #[test]
+    fn reword_branch_keeps_trees_and_authors() {
+        use testing::{commit, git, init_repo};
+        let dir = init_repo("reword");
+        let base = commit(&dir, "Base", "A <a@example.com>");
+        let first = commit(&dir, "First", "B <b@example.com>");
+        let second = commit(&dir, "Second", "C <c@example.com>");
+        let log = |rev: &str| git(&dir, &["log", "-1", "--format=%T %an %ae %ad", rev]);
+        let (first_info, second_info) = (log(&first), log(&second));
+
+        let repo = Repository::open_at(&dir).unwrap();
+        let new_first = repo
+            .reword_commit(&first, Some(&base), "JB-1 First\n")
+            .unwrap();
+        let new_second = repo
+            .reword_commit(&second, Some(&new_first), "JB-1 Second\n")
+            .unwrap();
+        repo.update_branch("main", &new_second, &second, "test")
+            .unwrap();
+
+        assert_eq!(new_second, git(&dir, &["rev-parse", "main"]));
+        assert_eq!(base, git(&dir, &["rev-parse", "main~2"]));
+        assert_eq!(first_info, log("main~1"));
+        assert_eq!(second_info, log("main"));
+        assert_eq!(
+            "JB-1 Second\nJB-1 First\nBase",
+            git(&dir, &["log", "--format=%s", "main"])
+        );
+        // The branch moved, updating from the old tip fails
+        assert!(
+            repo.update_branch("main", &second, &second, "test")
+                .is_err()
+        );
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
     #[test]
     fn branch_name_from_issue() {
         let branch_name =